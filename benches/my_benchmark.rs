@@ -8,7 +8,7 @@ mod minimax {
 
     fn orig_minimax(board: &pleco::Board, context: EvaluationContext) -> ValueType {
         if context.depth == 0 || board.checkmate() || board.stalemate() {
-            return board_value(board, context.depth);
+            return board_value(board, board.moves_played().into());
         }
 
         let mut experiment_board = board.clone();
@@ -50,7 +50,7 @@ mod minimax {
         mut context: EvaluationContext,
     ) -> ValueType {
         if context.depth == 0 || board.checkmate() || board.stalemate() {
-            return board_value(board, context.depth);
+            return board_value(board, board.moves_played().into());
         }
 
         let mut experiment_board = board.clone();
@@ -135,6 +135,8 @@ mod minimax {
         EvaluatedMove {
             m: best_move.unwrap(),
             value: best_value,
+            pv: Vec::new(),
+            stats: Default::default(),
         }
     }
 
@@ -162,7 +164,7 @@ mod negamax {
                 pleco::Player::White => 1,
                 pleco::Player::Black => -1,
             };
-            return color * board_value(board, context.depth);
+            return color * board_value(board, board.moves_played().into());
         }
 
         let mut experiment_board = board.clone();
@@ -191,7 +193,7 @@ mod negamax {
                 pleco::Player::White => 1,
                 pleco::Player::Black => -1,
             };
-            return color * board_value(board, context.depth);
+            return color * board_value(board, board.moves_played().into());
         }
 
         let mut experiment_board = board.clone();
@@ -244,6 +246,8 @@ mod negamax {
         EvaluatedMove {
             m: best_move.unwrap(),
             value: best_value,
+            pv: Vec::new(),
+            stats: Default::default(),
         }
     }
 
@@ -1,10 +1,21 @@
 use crate::board_value::board_value;
+use crate::draw::{insufficient_material, position_history, repetitions};
+use crate::move_ordering::{MoveOrdering, mvv_lva};
+use crate::search_limits::SearchLimits;
+use crate::search_stats::{SearchStats, StatsCounters};
+use crate::transposition_table::{Bound, Entry, TranspositionTable, position_key};
 use anyhow::Context;
 use pleco::Player;
 use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub type ValueType = i32;
 
+const INFINITE_VALUE: ValueType = 10_000_000;
+
+/// Search limits are checked every time this many nodes are visited
+const NODES_BETWEEN_CHECKS: u64 = 1024;
+
 #[derive(Debug, Clone, Default)]
 pub struct EvaluationContext {
     pub depth: u32,
@@ -12,80 +23,356 @@ pub struct EvaluationContext {
     pub beta: ValueType,
 }
 
+/// Tunable parts of the search
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Search quiet checking moves at the first ply of quiescence search
+    pub quiescence_checks: bool,
+    /// Break ties between equally ordered moves randomly for more varied play
+    pub shuffle_moves: bool,
+    /// Makes the search deterministic: root moves are searched one after
+    /// another instead of in parallel and the shuffle of `shuffle_moves`, if
+    /// set, is seeded. Results are still affected by time limits and positions
+    /// left in the transposition table.
+    pub seed: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct EvaluatedMove {
     pub m: pleco::BitMove,
     pub value: ValueType,
+    /// Principal variation: expected line of best play starting with `m`
+    pub pv: Vec<pleco::BitMove>,
+    /// Work done by the search until this move was found
+    pub stats: SearchStats,
 }
 
-fn shuffled_move_list(it: pleco::MoveList) -> Vec<pleco::BitMove> {
-    use rand::seq::SliceRandom;
-    let mut rng = rand::rng();
-    let mut y: Vec<pleco::BitMove> = it.iter().copied().collect();
-    y.shuffle(&mut rng);
-    y
+/// Everything shared by all nodes of a single search
+struct SearchState<'a> {
+    tt: &'a TranspositionTable,
+    options: &'a SearchOptions,
+    /// `moves_played` of the root board to find the ply of a node
+    root_moves_played: u16,
+    /// Keys of the positions played before the root and of the root itself
+    root_history: Vec<u64>,
+    limits: &'a SearchLimits,
+    start: std::time::Instant,
+    deadline: Option<std::time::Instant>,
+    stats: StatsCounters,
+    /// Set when a limit is reached. The current iteration is then discarded.
+    stop: AtomicBool,
+    /// Limits are not checked until the first iteration gives a move
+    has_answer: AtomicBool,
+}
+
+impl SearchState<'_> {
+    fn ply(&self, board: &pleco::Board) -> u32 {
+        (board.moves_played() - self.root_moves_played).into()
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+            || self
+                .limits
+                .cancellation
+                .as_ref()
+                .is_some_and(|x| x.is_cancelled())
+    }
+
+    /// Counts a node and returns true if the search must stop
+    fn visit_node(&self, board: &pleco::Board) -> bool {
+        let nodes = self.stats.nodes.fetch_add(1, Ordering::Relaxed) + 1;
+        self.stats
+            .selective_depth
+            .fetch_max(self.ply(board), Ordering::Relaxed);
+        if nodes.is_multiple_of(NODES_BETWEEN_CHECKS)
+            && self.has_answer.load(Ordering::Relaxed)
+        {
+            let out_of_nodes = self.limits.max_nodes.is_some_and(|x| nodes >= x);
+            let out_of_time = self
+                .deadline
+                .is_some_and(|x| std::time::Instant::now() >= x);
+            if out_of_nodes || out_of_time {
+                self.stop.store(true, Ordering::Relaxed);
+            }
+        }
+        self.stopped()
+    }
+
+    fn evaluate(&self, board: &pleco::Board) -> ValueType {
+        StatsCounters::increment(&self.stats.leaf_evaluations);
+        static_value(board, self.ply(board))
+    }
 }
 
+/// Searches the position to a fixed depth and returns the best move.
 pub fn get_best_move(
     board: &pleco::Board,
     depth: std::num::NonZeroU32,
 ) -> anyhow::Result<EvaluatedMove> {
+    iterative_deepening(
+        board,
+        &SearchLimits::depth(depth),
+        &TranspositionTable::default(),
+        &SearchOptions::default(),
+        |_, _| {},
+    )
+}
+
+/// Searches the position with depth 1, 2, ... until one of the `limits` is
+/// reached. Every completed iteration is reported to `on_iteration` together
+/// with its depth, so the caller always has an answer at hand. The best move of
+/// the previous iteration is searched first in the next one. An iteration
+/// interrupted by a limit or cancellation is thrown away and the last completed
+/// one is returned.
+///
+/// `tt` may be kept between calls: positions searched earlier are reused.
+pub fn iterative_deepening<F: FnMut(u32, &EvaluatedMove)>(
+    board: &pleco::Board,
+    limits: &SearchLimits,
+    tt: &TranspositionTable,
+    options: &SearchOptions,
+    mut on_iteration: F,
+) -> anyhow::Result<EvaluatedMove> {
+    let start = std::time::Instant::now();
+    let time_budget = limits.time_budget();
+    let state = SearchState {
+        tt,
+        options,
+        root_moves_played: board.moves_played(),
+        root_history: {
+            let mut keys = position_history(board);
+            keys.push(position_key(board));
+            keys
+        },
+        limits,
+        start,
+        deadline: time_budget.map(|x| start + x),
+        stats: StatsCounters::default(),
+        stop: AtomicBool::new(false),
+        has_answer: AtomicBool::new(false),
+    };
+    let mut best: Option<EvaluatedMove> = None;
+    for depth in 1..=limits.max_depth.get() {
+        let Some(evaluated) = search_root(board, depth, &state)? else {
+            break;
+        };
+        on_iteration(depth, &evaluated);
+        best = Some(evaluated);
+        state.has_answer.store(true, Ordering::Relaxed);
+        // The next iteration takes several times longer than this one
+        // and would most likely be interrupted
+        if time_budget.is_some_and(|x| start.elapsed() > x / 2) {
+            break;
+        }
+    }
+    let mut best =
+        best.context("Search was cancelled before depth 1 was completed")?;
+    // Include work of the interrupted iteration
+    best.stats = state.stats.snapshot(best.stats.depth, start.elapsed());
+    Ok(best)
+}
+
+/// Returns None if the search was stopped by a limit or cancelled
+fn search_root(
+    board: &pleco::Board,
+    depth: u32,
+    state: &SearchState,
+) -> anyhow::Result<Option<EvaluatedMove>> {
+    let tt = state.tt;
     let color = match board.turn() {
         Player::White => 1,
         Player::Black => -1,
     };
-    let context = EvaluationContext {
-        depth: depth.get(),
-        alpha: -10_000_000,
-        beta: 10_000_000,
+    let possible_moves = MoveOrdering::default().order_moves(
+        board,
+        &board.generate_moves(),
+        tt.probe(position_key(board), 0).and_then(|x| x.best_move),
+        0,
+        state.options.shuffle_moves,
+        state.options.seed,
+    );
+    let (&first_move, other_moves) =
+        possible_moves.split_first().context("No available moves")?;
+    let search_move = |ordering: &mut MoveOrdering, m, alpha: ValueType| {
+        let mut experiment_board = board.clone();
+        experiment_board.apply_move(m);
+        let mut pv = vec![m];
+        let value = -alpha_beta_impl(
+            &experiment_board,
+            EvaluationContext {
+                depth: depth - 1,
+                alpha: -INFINITE_VALUE,
+                beta: -alpha,
+            },
+            state,
+            ordering,
+            &mut state.root_history.clone(),
+            &mut pv,
+        );
+        (m, value, pv)
+    };
+    // Search the most promising move alone to get a lower bound for the others,
+    // then use par_iter for the rest of the first level of Negamax
+    let (mut best_move, mut best_value, mut best_pv) =
+        search_move(&mut MoveOrdering::default(), first_move, -INFINITE_VALUE);
+    let results: Vec<_> = if state.options.seed.is_some() {
+        let mut ordering = MoveOrdering::default();
+        other_moves
+            .iter()
+            .map(|&m| search_move(&mut ordering, m, best_value))
+            .collect()
+    } else {
+        other_moves
+            .par_iter()
+            .map_init(MoveOrdering::default, |ordering, &m| {
+                search_move(ordering, m, best_value)
+            })
+            .collect()
     };
-    let possible_moves = shuffled_move_list(board.generate_moves());
-    // Use par_iter for the first level of Negamax to remember moves
-    let (best_move, mut best_value) = possible_moves
-        .par_iter()
-        .cloned()
-        .map(|m| {
-            let mut experiment_board = board.clone();
-            experiment_board.apply_move(m);
-            let value = -alpha_beta_impl(
-                &experiment_board,
-                EvaluationContext {
-                    depth: context.depth - 1,
-                    ..context
-                },
-            );
-            experiment_board.undo_move();
-            (m, value)
-        })
-        .max_by_key(|(_, value)| *value)
-        .context("No available moves")?;
+    // The first of equal moves in the move order wins whatever the thread
+    // which searched it
+    if let Some((m, value, pv)) =
+        results.into_iter().rev().max_by_key(|(_, value, _)| *value)
+        && value > best_value
+    {
+        best_move = m;
+        best_value = value;
+        best_pv = pv;
+    }
+    if state.stopped() {
+        return Ok(None);
+    }
+    tt.store(
+        position_key(board),
+        Entry {
+            depth,
+            bound: Bound::Exact,
+            value: best_value,
+            best_move: Some(best_move),
+        },
+        0,
+    );
     // Invert color back if current player is minimizer
     best_value *= color;
-    Ok(EvaluatedMove {
+    Ok(Some(EvaluatedMove {
         m: best_move,
         value: best_value,
-    })
+        pv: best_pv,
+        stats: state.stats.snapshot(depth, state.start.elapsed()),
+    }))
+}
+
+/// Follows best moves stored in `tt` from `board` for at most `max_len` moves.
+/// Stops at a move which is not legal (hash collision) or a repeated position.
+fn pv_from_tt(
+    board: &pleco::Board,
+    tt: &TranspositionTable,
+    max_len: u32,
+) -> Vec<pleco::BitMove> {
+    let mut pv = Vec::new();
+    let mut keys = vec![position_key(board)];
+    let mut experiment_board = board.clone();
+    while pv.len() < max_len as usize {
+        let Some(m) = tt
+            .probe(position_key(&experiment_board), 0)
+            .and_then(|x| x.best_move)
+            .filter(|&m| experiment_board.generate_moves().contains(&m))
+        else {
+            break;
+        };
+        experiment_board.apply_move(m);
+        if keys.contains(&position_key(&experiment_board)) {
+            break;
+        }
+        keys.push(position_key(&experiment_board));
+        pv.push(m);
+    }
+    pv
+}
+
+/// Value of the position at `ply` from the root for the side to move
+fn static_value(board: &pleco::Board, ply: u32) -> ValueType {
+    let color = match board.turn() {
+        Player::White => 1,
+        Player::Black => -1,
+    };
+    // Value of a minimizer player must be negated
+    color * board_value(board, ply)
+}
+
+/// Repetition of a position in the game or in the search line, fifty-move rule
+/// or insufficient material. A single repetition is enough: the side which
+/// could avoid it would have done so at the first occurrence.
+fn is_draw(board: &pleco::Board, history: &[u64]) -> bool {
+    board.rule_50() >= 100
+        || repetitions(board, history) > 0
+        || insufficient_material(board)
 }
 
+/// Moves of the principal variation found below `board` are appended to `pv`.
+/// `history` holds the keys of the positions played before `board`.
 fn alpha_beta_impl(
     board: &pleco::Board,
     mut context: EvaluationContext,
+    state: &SearchState,
+    ordering: &mut MoveOrdering,
+    history: &mut Vec<u64>,
+    pv: &mut Vec<pleco::BitMove>,
 ) -> ValueType {
-    if context.depth == 0 || board.checkmate() {
-        let color = match board.turn() {
-            Player::White => 1,
-            Player::Black => -1,
-        };
-        // Value of a minimizer player must be negated
-        return color * board_value(board, context.depth);
+    if state.visit_node(board) {
+        return 0;
+    }
+    if board.checkmate() {
+        return state.evaluate(board);
     }
-    if board.stalemate() {
+    if board.stalemate() || is_draw(board, history) {
         return 0;
     }
+    if context.depth == 0 {
+        return quiescence(board, context.alpha, context.beta, 0, state);
+    }
+    let tt = state.tt;
+    let key = position_key(board);
+    let ply = state.ply(board);
+    let entry = tt.probe(key, ply);
+    if entry.is_some() {
+        StatsCounters::increment(&state.stats.tt_hits);
+    }
+    if let Some(entry) = entry
+        && entry.depth >= context.depth
+    {
+        match entry.bound {
+            Bound::Exact => {
+                pv.extend(pv_from_tt(board, tt, context.depth));
+                return entry.value;
+            }
+            Bound::Lower => context.alpha = context.alpha.max(entry.value),
+            Bound::Upper => context.beta = context.beta.min(entry.value),
+        }
+        if context.alpha >= context.beta {
+            return entry.value;
+        }
+    }
+    let original_alpha = context.alpha;
+    let possible_moves = ordering.order_moves(
+        board,
+        &board.generate_moves(),
+        entry.and_then(|x| x.best_move),
+        ply,
+        state.options.shuffle_moves,
+        state.options.seed,
+    );
     let mut experiment_board = board.clone();
-    let mut best_value = -10_000_000;
-    for m in shuffled_move_list(board.generate_moves()) {
+    let mut best_value = -INFINITE_VALUE;
+    let mut best_move = None;
+    let mut best_pv = Vec::new();
+    let mut child_pv = Vec::new();
+    history.push(key);
+    for (i, m) in possible_moves.into_iter().enumerate() {
         experiment_board.apply_move(m);
+        child_pv.clear();
+        child_pv.push(m);
         let value = -alpha_beta_impl(
             &experiment_board,
             EvaluationContext {
@@ -93,11 +380,102 @@ fn alpha_beta_impl(
                 alpha: -context.beta,
                 beta: -context.alpha,
             },
+            state,
+            ordering,
+            history,
+            &mut child_pv,
         );
         experiment_board.undo_move();
-        best_value = best_value.max(value);
+        if state.stopped() {
+            history.pop();
+            return 0;
+        }
+        if value > best_value {
+            best_value = value;
+            best_move = Some(m);
+            std::mem::swap(&mut best_pv, &mut child_pv);
+        }
         context.alpha = context.alpha.max(best_value);
         if context.alpha >= context.beta {
+            ordering.update_on_cutoff(board, m, ply, context.depth);
+            StatsCounters::increment(&state.stats.beta_cutoffs);
+            if i == 0 {
+                StatsCounters::increment(&state.stats.first_move_cutoffs);
+            }
+            break;
+        }
+    }
+    history.pop();
+    let bound = if best_value <= original_alpha {
+        Bound::Upper
+    } else if best_value >= context.beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    tt.store(
+        key,
+        Entry {
+            depth: context.depth,
+            bound,
+            value: best_value,
+            best_move,
+        },
+        ply,
+    );
+    pv.append(&mut best_pv);
+    best_value
+}
+
+/// Resolves captures and promotions at the leaves of the main search so that
+/// the static value is never taken in the middle of an exchange. The side to
+/// move may stand pat unless it is in check, in which case all evasions are
+/// searched. `ply` counts plies from the start of the quiescence search.
+fn quiescence(
+    board: &pleco::Board,
+    mut alpha: ValueType,
+    beta: ValueType,
+    ply: u32,
+    state: &SearchState,
+) -> ValueType {
+    if state.visit_node(board) || insufficient_material(board) {
+        return 0;
+    }
+    let in_check = board.in_check();
+    let mut best_value = -INFINITE_VALUE;
+    let mut possible_moves = if in_check {
+        let evasions = board.generate_moves();
+        if evasions.is_empty() {
+            return state.evaluate(board);
+        }
+        evasions.to_vec()
+    } else {
+        best_value = state.evaluate(board);
+        if best_value >= beta {
+            return best_value;
+        }
+        alpha = alpha.max(best_value);
+        board
+            .generate_moves_of_type(pleco::core::GenTypes::Captures)
+            .to_vec()
+    };
+    if !in_check && ply == 0 && state.options.quiescence_checks {
+        possible_moves.extend(
+            board.generate_moves_of_type(pleco::core::GenTypes::QuietChecks),
+        );
+    }
+    possible_moves.sort_by_key(|&m| -mvv_lva(board, m));
+    let mut experiment_board = board.clone();
+    for m in possible_moves {
+        experiment_board.apply_move(m);
+        let value = -quiescence(&experiment_board, -beta, -alpha, ply + 1, state);
+        experiment_board.undo_move();
+        if state.stopped() {
+            return 0;
+        }
+        best_value = best_value.max(value);
+        alpha = alpha.max(best_value);
+        if alpha >= beta {
             break;
         }
     }
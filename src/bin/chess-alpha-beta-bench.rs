@@ -0,0 +1,40 @@
+use chess_alpha_beta::bench::bench;
+use chess_alpha_beta::transposition_table::TranspositionTable;
+
+/// Searches fixed positions to a fixed depth. The total number of nodes is a
+/// signature of the search: it changes only when the search does.
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Depth of the search in every position
+    #[arg(short, long, default_value = "5")]
+    depth: std::num::NonZeroU32,
+
+    /// Size of the transposition table in megabytes
+    #[arg(long, default_value_t = TranspositionTable::DEFAULT_SIZE_MB)]
+    hash_size: usize,
+}
+
+fn main() -> anyhow::Result<()> {
+    use clap::Parser;
+    let args = Args::parse();
+
+    let positions = bench(args.depth, args.hash_size)?;
+    for (i, x) in positions.iter().enumerate() {
+        println!(
+            "Position {}/{}: {}, bestmove = {}, nodes = {}",
+            i + 1,
+            positions.len(),
+            x.fen,
+            x.best_move,
+            x.nodes
+        );
+    }
+    let nodes: u64 = positions.iter().map(|x| x.nodes).sum();
+    let elapsed: std::time::Duration = positions.iter().map(|x| x.elapsed).sum();
+    println!();
+    println!("Total time (ms): {}", elapsed.as_millis());
+    println!("Nodes searched: {nodes}");
+    println!("Nodes/second: {:.0}", nodes as f64 / elapsed.as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use chess_alpha_beta::book::{BookBuilder, write_book};
+use chess_alpha_beta::pgn::parse_pgn;
+
+use anyhow::Context;
+
+/// Builds an opening book in Polyglot format from PGN files
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// PGN files with games to count
+    #[arg(required = true)]
+    pgn: Vec<std::path::PathBuf>,
+
+    /// Book file to write
+    #[arg(short, long)]
+    output: std::path::PathBuf,
+
+    /// Number of half moves of every game to count
+    #[arg(long, default_value = "20")]
+    max_ply: usize,
+
+    /// Leave out moves played in fewer games
+    #[arg(long, default_value = "1")]
+    min_games: u32,
+}
+
+fn main() -> anyhow::Result<()> {
+    use clap::Parser;
+    let args = Args::parse();
+
+    let mut builder = BookBuilder::default();
+    let (mut counted, mut skipped) = (0, 0);
+    for path in &args.pgn {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let games = parse_pgn(&text)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+        for (i, game) in games.iter().enumerate() {
+            match builder.add_game(game, args.max_ply) {
+                Ok(true) => counted += 1,
+                Ok(false) => skipped += 1,
+                Err(e) => {
+                    println!(
+                        "Skipping game {} of '{}': {:#}",
+                        i + 1,
+                        path.display(),
+                        e
+                    );
+                    skipped += 1;
+                }
+            }
+        }
+    }
+    let entries = builder.entries(args.min_games);
+    write_book(&args.output, &entries)?;
+    println!("Counted {counted} games, skipped {skipped}");
+    println!(
+        "Wrote {} moves of {} positions to '{}'",
+        entries.len(),
+        entries.chunk_by(|a, b| a.key == b.key).count(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::perft::{divide, perft};
+
+/// Counts leaf nodes of the legal move tree to verify the move generator
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+    /// Depth of the move tree
+    depth: u32,
+
+    /// Position to count from, the initial position by default
+    #[arg(long)]
+    fen: Option<String>,
+
+    /// Print the number of nodes after every legal move
+    #[arg(long)]
+    divide: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    use clap::Parser;
+    let args = Args::parse();
+    let board = match &args.fen {
+        Some(fen) => parse_fen(fen)?,
+        None => pleco::Board::start_pos(),
+    };
+
+    let start = std::time::Instant::now();
+    let nodes = if args.divide && args.depth > 0 {
+        let mut moves = divide(&board, args.depth);
+        moves.sort_by_key(|(m, _)| m.to_string());
+        for (m, nodes) in &moves {
+            println!("{m}: {nodes}");
+        }
+        println!();
+        moves.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        perft(&board, args.depth)
+    };
+    let elapsed = start.elapsed();
+    println!("Nodes searched: {nodes}");
+    println!(
+        "Time: {:.3}s, nps: {:.0}",
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
@@ -1,46 +1,174 @@
 use anyhow::Context;
 
-use axum::extract::Json;
+use axum::extract::{Json, State};
 use axum::http::header;
 use axum::http::{Method, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
-use chess_alpha_beta::alpha_beta::{ValueType, get_best_move};
+use chess_alpha_beta::alpha_beta::{SearchOptions, ValueType, iterative_deepening};
+use chess_alpha_beta::book::{BookSelection, OpeningBook};
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::search_limits::{CancellationToken, Clock, SearchLimits};
+use chess_alpha_beta::search_stats::SearchStats;
+use chess_alpha_beta::transposition_table::TranspositionTable;
 
 #[derive(serde::Deserialize)]
 struct GetBestMoveRequest {
     search_depth: std::num::NonZeroU32,
     fen: String,
+    #[serde(default)]
+    shuffle_moves: bool,
+    /// Deterministic search with a fresh transposition table. Seeds the shuffle
+    /// only if `shuffle_moves` is set.
+    seed: Option<u64>,
+    max_nodes: Option<u64>,
+    move_time_ms: Option<u64>,
+    /// Remaining time of the side to move
+    clock_ms: Option<u64>,
+    #[serde(default)]
+    increment_ms: u64,
+    /// Include search statistics in the response
+    #[serde(default)]
+    stats: bool,
+    /// Search even if the position is in the opening book
+    #[serde(default)]
+    no_book: bool,
+}
+
+#[derive(Clone)]
+struct AppState {
+    tt: std::sync::Arc<TranspositionTable>,
+    /// Upper limit of time spent on a single request
+    max_move_time: std::time::Duration,
+    book: Option<std::sync::Arc<OpeningBook>>,
+    book_selection: BookSelection,
 }
 
 #[derive(serde::Serialize)]
 struct GetBestMoveResponse {
     m: String,
     value: ValueType,
+    /// Expected line of best play starting with `m`
+    pv: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stats: Option<SearchStatsResponse>,
+    /// `m` is taken from the opening book without searching
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    book: bool,
+}
+
+#[derive(serde::Serialize)]
+struct SearchStatsResponse {
+    nodes: u64,
+    leaf_evaluations: u64,
+    beta_cutoffs: u64,
+    first_move_cutoff_ratio: f64,
+    tt_hits: u64,
+    elapsed_ms: u128,
+    nodes_per_second: u64,
+    depth: u32,
+    selective_depth: u32,
+}
+
+impl From<&SearchStats> for SearchStatsResponse {
+    fn from(x: &SearchStats) -> Self {
+        Self {
+            nodes: x.nodes,
+            leaf_evaluations: x.leaf_evaluations,
+            beta_cutoffs: x.beta_cutoffs,
+            first_move_cutoff_ratio: x.first_move_cutoff_ratio(),
+            tt_hits: x.tt_hits,
+            elapsed_ms: x.elapsed.as_millis(),
+            nodes_per_second: x.nodes_per_second(),
+            depth: x.depth,
+            selective_depth: x.selective_depth,
+        }
+    }
+}
+
+/// Stops the search when the request future is dropped, e.g. when the client
+/// disconnects
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
 }
 
-async fn api_get_best_move(Json(json): Json<GetBestMoveRequest>) -> Response {
+async fn api_get_best_move(
+    State(state): State<AppState>,
+    Json(json): Json<GetBestMoveRequest>,
+) -> Response {
     if json.search_depth.get() > 10 {
         return (StatusCode::BAD_REQUEST, "search_depth is too large!")
             .into_response();
     }
 
-    let board = match pleco::Board::from_fen(&json.fen) {
+    let board = match parse_fen(&json.fen) {
         Ok(x) => x,
         Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                format!("Failed to parse FEN: {e:?}"),
-            )
-                .into_response();
+            return (StatusCode::BAD_REQUEST, format!("{e:#}")).into_response();
         }
     };
-    match get_best_move(&board, json.search_depth) {
+    if let Some(book) = &state.book
+        && !json.no_book
+        && let Some(m) = book.choose(&board, state.book_selection, json.seed)
+    {
+        return axum::response::Json(GetBestMoveResponse {
+            m: m.to_string(),
+            value: 0,
+            pv: vec![m.to_string()],
+            stats: None,
+            book: true,
+        })
+        .into_response();
+    }
+    let move_time = json
+        .move_time_ms
+        .map(std::time::Duration::from_millis)
+        .map_or(state.max_move_time, |x| x.min(state.max_move_time));
+    let cancellation = CancellationToken::default();
+    let _cancel_on_drop = CancelOnDrop(cancellation.clone());
+    let limits = SearchLimits {
+        max_depth: json.search_depth,
+        max_nodes: json.max_nodes,
+        move_time: Some(move_time),
+        clock: json.clock_ms.map(|x| Clock {
+            remaining: std::time::Duration::from_millis(x),
+            increment: std::time::Duration::from_millis(json.increment_ms),
+            moves_to_go: None,
+        }),
+        cancellation: Some(cancellation.clone()),
+    };
+    let options = SearchOptions {
+        shuffle_moves: json.shuffle_moves,
+        seed: json.seed,
+        ..Default::default()
+    };
+    let with_stats = json.stats;
+    // The shared table would make the result depend on earlier requests
+    let tt = match json.seed {
+        Some(_) => std::sync::Arc::new(TranspositionTable::default()),
+        None => state.tt.clone(),
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        iterative_deepening(&board, &limits, &tt, &options, |depth, x| {
+            log::debug!("depth {depth}: move = {}, value = {}", x.m, x.value);
+        })
+    })
+    .await
+    .context("Search task failed")
+    .and_then(|x| x);
+    match result {
         Ok(ok) => axum::response::Json(GetBestMoveResponse {
             m: ok.m.to_string(),
             value: ok.value,
+            pv: ok.pv.iter().map(|x| x.to_string()).collect(),
+            stats: with_stats.then(|| (&ok.stats).into()),
+            book: false,
         })
         .into_response(),
         Err(e) => (
@@ -66,6 +194,22 @@ struct Args {
     /// Use systemd_journal_logger instead of env_logger
     #[arg(short, long)]
     journald: bool,
+
+    /// Size of the transposition table shared by all requests in megabytes
+    #[arg(long, default_value_t = TranspositionTable::DEFAULT_SIZE_MB)]
+    hash_size: usize,
+
+    /// Upper limit of time spent on a single request in milliseconds
+    #[arg(long, default_value = "30000")]
+    max_move_time: u64,
+
+    /// Opening book in Polyglot format, e.g. written by chess-alpha-beta-book
+    #[arg(long)]
+    book: Option<std::path::PathBuf>,
+
+    /// How one of the book moves is picked
+    #[arg(long, default_value = "weighted")]
+    book_selection: BookSelection,
 }
 
 fn init_logging(args: &Args) -> anyhow::Result<()> {
@@ -87,6 +231,18 @@ async fn main() -> anyhow::Result<()> {
     use clap::Parser;
     let args = Args::parse();
     init_logging(&args).context("Failed to init logging")?;
+    let book = match &args.book {
+        Some(path) => {
+            let book = OpeningBook::open(path)?;
+            log::info!(
+                "Loaded {} book entries from '{}'",
+                book.len(),
+                path.display()
+            );
+            Some(std::sync::Arc::new(book))
+        }
+        None => None,
+    };
 
     let app = axum::Router::new()
         .route("/api/get_best_move", post(api_get_best_move))
@@ -98,7 +254,13 @@ async fn main() -> anyhow::Result<()> {
                 }))
                 .allow_methods([Method::GET, Method::POST])
                 .allow_headers([header::CONTENT_TYPE]),
-        );
+        )
+        .with_state(AppState {
+            tt: std::sync::Arc::new(TranspositionTable::new(args.hash_size)),
+            max_move_time: std::time::Duration::from_millis(args.max_move_time),
+            book,
+            book_selection: args.book_selection,
+        });
 
     let listener = tokio::net::TcpListener::bind(&args.bind_addr)
         .await
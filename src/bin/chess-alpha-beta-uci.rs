@@ -0,0 +1,291 @@
+use anyhow::Context;
+use std::io::BufRead;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chess_alpha_beta::alpha_beta::{
+    EvaluatedMove, SearchOptions, iterative_deepening,
+};
+use chess_alpha_beta::board_value::plies_to_mate;
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::search_limits::{CancellationToken, Clock, SearchLimits};
+use chess_alpha_beta::transposition_table::TranspositionTable;
+
+/// Depth of `go infinite` and `go` without a depth
+const MAX_DEPTH: u32 = 64;
+
+/// Running search started by `go`
+struct Search {
+    cancellation: CancellationToken,
+    handle: std::thread::JoinHandle<()>,
+}
+
+struct Engine {
+    board: pleco::Board,
+    /// Error of the last "position" command. "go" answers it with a null move
+    /// instead of searching the previous position.
+    invalid_position: Option<String>,
+    tt: Arc<TranspositionTable>,
+    options: SearchOptions,
+    search: Option<Search>,
+}
+
+/// Score of a search result from the side to move as UCI expects it
+fn uci_score(board: &pleco::Board, value: i32) -> String {
+    let value = match board.turn() {
+        pleco::Player::White => value,
+        pleco::Player::Black => -value,
+    };
+    match plies_to_mate(value) {
+        Some(plies) => format!("mate {}", (plies + plies.signum()) / 2),
+        None => format!("cp {value}"),
+    }
+}
+
+fn print_info(board: &pleco::Board, depth: u32, x: &EvaluatedMove) {
+    let pv: Vec<String> = x.pv.iter().map(|m| m.to_string()).collect();
+    println!(
+        "info depth {} seldepth {} score {} nodes {} nps {} time {} pv {}",
+        depth,
+        x.stats.selective_depth,
+        uci_score(board, x.value),
+        x.stats.nodes,
+        x.stats.nodes_per_second(),
+        x.stats.elapsed.as_millis(),
+        pv.join(" ")
+    );
+}
+
+/// Parses "position [startpos | fen <fen>] [moves <move>...]"
+fn parse_position(args: &[&str]) -> anyhow::Result<pleco::Board> {
+    let moves_start = args
+        .iter()
+        .position(|x| *x == "moves")
+        .unwrap_or(args.len());
+    let mut board = match args.first() {
+        Some(&"startpos") => pleco::Board::start_pos(),
+        Some(&"fen") => {
+            let fen = args[1..moves_start].join(" ");
+            parse_fen(&fen)?
+        }
+        _ => anyhow::bail!("Expected 'startpos' or 'fen'"),
+    };
+    for m in args.iter().skip(moves_start + 1) {
+        anyhow::ensure!(board.apply_uci_move(m), "Illegal move '{m}'");
+    }
+    Ok(board)
+}
+
+/// Parses arguments of "go" into search limits. Returns true as the second
+/// value for "go infinite".
+fn parse_go(
+    board: &pleco::Board,
+    args: &[&str],
+) -> anyhow::Result<(SearchLimits, bool)> {
+    let mut limits = SearchLimits::depth(MAX_DEPTH.try_into().unwrap());
+    let mut infinite = false;
+    let mut times = [None; 2];
+    let mut increments = [Duration::ZERO; 2];
+    let mut moves_to_go = None;
+    let mut it = args.iter();
+    while let Some(&name) = it.next() {
+        let mut value = || -> anyhow::Result<u64> {
+            it.next()
+                .with_context(|| format!("Missing value of '{name}'"))?
+                .parse()
+                .with_context(|| format!("Invalid value of '{name}'"))
+        };
+        match name {
+            "depth" => {
+                limits.max_depth = u32::try_from(value()?)?
+                    .clamp(1, MAX_DEPTH)
+                    .try_into()
+                    .unwrap()
+            }
+            "nodes" => limits.max_nodes = Some(value()?),
+            "movetime" => limits.move_time = Some(Duration::from_millis(value()?)),
+            "wtime" => times[0] = Some(Duration::from_millis(value()?)),
+            "btime" => times[1] = Some(Duration::from_millis(value()?)),
+            "winc" => increments[0] = Duration::from_millis(value()?),
+            "binc" => increments[1] = Duration::from_millis(value()?),
+            "movestogo" => moves_to_go = Some(u32::try_from(value()?)?),
+            "infinite" => infinite = true,
+            // Unsupported arguments without a value
+            "ponder" => {}
+            _ => anyhow::bail!("Unsupported go argument '{name}'"),
+        }
+    }
+    let side = board.turn() as usize;
+    limits.clock = times[side].map(|remaining| Clock {
+        remaining,
+        increment: increments[side],
+        moves_to_go,
+    });
+    Ok((limits, infinite))
+}
+
+impl Engine {
+    fn new() -> Self {
+        Self {
+            board: pleco::Board::start_pos(),
+            invalid_position: None,
+            tt: Arc::new(TranspositionTable::default()),
+            options: SearchOptions::default(),
+            search: None,
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancellation.cancel();
+            search.handle.join().expect("Search thread panicked");
+        }
+    }
+
+    fn go(&mut self, args: &[&str]) -> anyhow::Result<()> {
+        self.stop();
+        if let Some(e) = &self.invalid_position {
+            println!("info string Invalid position: {e}");
+            println!("bestmove 0000");
+            return Ok(());
+        }
+        let (mut limits, infinite) = parse_go(&self.board, args)?;
+        let cancellation = CancellationToken::default();
+        limits.cancellation = Some(cancellation.clone());
+        // Keeps the moves played for repetition detection
+        let board = self.board.parallel_clone();
+        let tt = self.tt.clone();
+        let options = self.options.clone();
+        let handle = std::thread::spawn(move || {
+            let result =
+                iterative_deepening(&board, &limits, &tt, &options, |d, x| {
+                    print_info(&board, d, x)
+                });
+            // In infinite mode bestmove is only sent after "stop"
+            while infinite && !limits.cancellation.as_ref().unwrap().is_cancelled() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            match result {
+                Ok(best) => match best.pv.get(1) {
+                    Some(ponder) => {
+                        println!("bestmove {} ponder {}", best.m, ponder)
+                    }
+                    None => println!("bestmove {}", best.m),
+                },
+                Err(e) => {
+                    println!("info string {e}");
+                    println!("bestmove 0000");
+                }
+            }
+        });
+        self.search = Some(Search {
+            cancellation,
+            handle,
+        });
+        Ok(())
+    }
+
+    /// Handles "setoption name <name> [value <value>]"
+    fn set_option(&mut self, args: &[&str]) -> anyhow::Result<()> {
+        let value_start = args
+            .iter()
+            .position(|x| *x == "value")
+            .unwrap_or(args.len());
+        let name = args.get(1..value_start).unwrap_or_default().join(" ");
+        let value = args.get(value_start + 1..).unwrap_or_default().join(" ");
+        match name.to_lowercase().as_str() {
+            "hash" => {
+                self.stop();
+                let size_mb = value.parse().context("Invalid Hash value")?;
+                self.tt = Arc::new(TranspositionTable::new(size_mb));
+            }
+            "quiescencechecks" => self.options.quiescence_checks = value == "true",
+            "shufflemoves" => self.options.shuffle_moves = value == "true",
+            "seed" => {
+                self.options.seed = match value.as_str() {
+                    "" | "<empty>" => None,
+                    _ => Some(value.parse().context("Invalid Seed value")?),
+                }
+            }
+            _ => anyhow::bail!("Unknown option '{name}'"),
+        }
+        Ok(())
+    }
+
+    /// Returns false on "quit"
+    fn handle_command(&mut self, line: &str) -> anyhow::Result<bool> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(true);
+        };
+        match command {
+            "uci" => {
+                println!(
+                    "id name {} {}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                );
+                println!("id author vehlwn");
+                println!(
+                    "option name Hash type spin default {} min 1 max 65536",
+                    TranspositionTable::DEFAULT_SIZE_MB
+                );
+                println!("option name QuiescenceChecks type check default false");
+                println!("option name ShuffleMoves type check default false");
+                println!("option name Seed type string default <empty>");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.stop();
+                self.tt.clear();
+                self.board = pleco::Board::start_pos();
+                self.invalid_position = None;
+            }
+            "position" => {
+                self.stop();
+                match parse_position(args) {
+                    Ok(board) => {
+                        self.board = board;
+                        self.invalid_position = None;
+                    }
+                    Err(e) => {
+                        self.invalid_position = Some(format!("{e:#}"));
+                        return Err(e);
+                    }
+                }
+            }
+            "go" => self.go(args)?,
+            "stop" => self.stop(),
+            "setoption" => self.set_option(args)?,
+            "quit" => {
+                self.stop();
+                return Ok(false);
+            }
+            _ => anyhow::bail!("Unknown command '{command}'"),
+        }
+        Ok(true)
+    }
+}
+
+/// UCI protocol front-end for chess-alpha-beta. Reads commands from stdin and
+/// writes responses to stdout.
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {}
+
+fn main() -> anyhow::Result<()> {
+    use clap::Parser;
+    Args::parse();
+    let mut engine = Engine::new();
+    for line in std::io::stdin().lock().lines() {
+        let line = line.context("Failed to read stdin")?;
+        match engine.handle_command(&line) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => println!("info string {e:#}"),
+        }
+    }
+    engine.stop();
+    Ok(())
+}
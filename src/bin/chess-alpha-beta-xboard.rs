@@ -0,0 +1,347 @@
+use anyhow::Context;
+use std::io::BufRead;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use chess_alpha_beta::alpha_beta::{
+    EvaluatedMove, SearchOptions, iterative_deepening,
+};
+use chess_alpha_beta::board_value::plies_to_mate;
+use chess_alpha_beta::draw::draw_reason;
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::search_limits::{CancellationToken, Clock, SearchLimits};
+use chess_alpha_beta::transposition_table::TranspositionTable;
+
+/// Depth used when the search is limited by time
+const MAX_DEPTH: u32 = 64;
+/// Depth used without "sd", "st" and "level", same as the CLI default
+const DEFAULT_DEPTH: u32 = 6;
+
+enum Event {
+    Line(String),
+    /// Result of the search with the given id
+    SearchDone(u64, anyhow::Result<EvaluatedMove>),
+}
+
+/// Time control set by "level"
+struct Level {
+    /// Moves per time control, 0 for the whole game
+    moves_per_control: u32,
+    increment: Duration,
+}
+
+/// Running search started by "go" or a user move
+struct Search {
+    id: u64,
+    cancellation: CancellationToken,
+}
+
+struct Engine {
+    board: pleco::Board,
+    tt: Arc<TranspositionTable>,
+    options: SearchOptions,
+    /// Side played by the engine, None in force mode
+    engine_side: Option<pleco::Player>,
+    max_depth: Option<u32>,
+    move_time: Option<Duration>,
+    level: Option<Level>,
+    /// Engine clock set by "time"
+    remaining: Option<Duration>,
+    post: bool,
+    search: Option<Search>,
+    next_search_id: u64,
+    events: mpsc::Sender<Event>,
+}
+
+/// Score of a search result from the side to move in centipawns. Mates are
+/// reported as 100000 + moves to mate.
+fn xboard_score(board: &pleco::Board, value: i32) -> i32 {
+    let value = match board.turn() {
+        pleco::Player::White => value,
+        pleco::Player::Black => -value,
+    };
+    match plies_to_mate(value) {
+        Some(plies) => plies.signum() * 100_000 + (plies + plies.signum()) / 2,
+        None => value,
+    }
+}
+
+/// Returns the result string if the game is over
+fn game_result(board: &pleco::Board) -> Option<String> {
+    if board.checkmate() {
+        Some(
+            match board.turn() {
+                pleco::Player::White => "0-1 {Black mates}",
+                pleco::Player::Black => "1-0 {White mates}",
+            }
+            .to_string(),
+        )
+    } else if board.stalemate() {
+        Some("1/2-1/2 {Stalemate}".to_string())
+    } else {
+        draw_reason(board).map(|x| format!("1/2-1/2 {{Draw by {x}}}"))
+    }
+}
+
+/// Parses "level" base time which is either minutes or "minutes:seconds"
+fn parse_base_time(s: &str) -> anyhow::Result<Duration> {
+    let (minutes, seconds) = s.split_once(':').unwrap_or((s, "0"));
+    Ok(Duration::from_secs(
+        minutes.parse::<u64>()? * 60 + seconds.parse::<u64>()?,
+    ))
+}
+
+impl Engine {
+    fn new(events: mpsc::Sender<Event>) -> Self {
+        Self {
+            board: pleco::Board::start_pos(),
+            tt: Arc::new(TranspositionTable::default()),
+            options: SearchOptions::default(),
+            engine_side: Some(pleco::Player::Black),
+            max_depth: None,
+            move_time: None,
+            level: None,
+            remaining: None,
+            post: false,
+            search: None,
+            next_search_id: 0,
+            events,
+        }
+    }
+
+    /// Stops the running search. Its result is thrown away.
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancellation.cancel();
+        }
+    }
+
+    fn search_limits(&self) -> SearchLimits {
+        let timed = self.move_time.is_some() || self.level.is_some();
+        let max_depth =
+            self.max_depth
+                .unwrap_or(if timed { MAX_DEPTH } else { DEFAULT_DEPTH });
+        let mut limits = SearchLimits::depth(max_depth.max(1).try_into().unwrap());
+        limits.move_time = self.move_time;
+        if let Some(level) = &self.level
+            && let Some(remaining) = self.remaining
+        {
+            let moves_to_go = (level.moves_per_control > 0).then(|| {
+                let moves_made = u32::from(self.board.moves_played() / 2);
+                level.moves_per_control - moves_made % level.moves_per_control
+            });
+            limits.clock = Some(Clock {
+                remaining,
+                increment: level.increment,
+                moves_to_go,
+            });
+        }
+        limits
+    }
+
+    /// Starts searching for a move of the side to move if the engine plays it
+    fn start_search_if_engine_turn(&mut self) {
+        if self.engine_side != Some(self.board.turn()) || self.search.is_some() {
+            return;
+        }
+        if let Some(result) = game_result(&self.board) {
+            println!("{result}");
+            return;
+        }
+        let cancellation = CancellationToken::default();
+        let mut limits = self.search_limits();
+        limits.cancellation = Some(cancellation.clone());
+        let id = self.next_search_id;
+        self.next_search_id += 1;
+        self.search = Some(Search { id, cancellation });
+        // Keeps the moves played for repetition detection
+        let board = self.board.parallel_clone();
+        let tt = self.tt.clone();
+        let options = self.options.clone();
+        let post = self.post;
+        let events = self.events.clone();
+        std::thread::spawn(move || {
+            let result =
+                iterative_deepening(&board, &limits, &tt, &options, |d, x| {
+                    if post {
+                        let pv: Vec<String> =
+                            x.pv.iter().map(|m| m.to_string()).collect();
+                        println!(
+                            "{} {} {} {} {}",
+                            d,
+                            xboard_score(&board, x.value),
+                            x.stats.elapsed.as_millis() / 10,
+                            x.stats.nodes,
+                            pv.join(" ")
+                        );
+                    }
+                });
+            // Receiver is gone only when the program exits
+            let _ = events.send(Event::SearchDone(id, result));
+        });
+    }
+
+    fn on_search_done(&mut self, id: u64, result: anyhow::Result<EvaluatedMove>) {
+        if self.search.as_ref().is_none_or(|x| x.id != id) {
+            // Result of a stopped search
+            return;
+        }
+        self.search = None;
+        match result {
+            Ok(best) => {
+                self.board.apply_move(best.m);
+                println!("move {}", best.m);
+                if let Some(result) = game_result(&self.board) {
+                    println!("{result}");
+                }
+            }
+            Err(e) => println!("Error (search failed): {e}"),
+        }
+    }
+
+    fn user_move(&mut self, m: &str) {
+        self.stop();
+        if !self.board.apply_uci_move(m) {
+            println!("Illegal move: {m}");
+            return;
+        }
+        self.start_search_if_engine_turn();
+    }
+
+    fn undo(&mut self, count: u16) {
+        self.stop();
+        for _ in 0..count.min(self.board.ply()) {
+            self.board.undo_move();
+        }
+    }
+
+    /// Returns false on "quit"
+    fn handle_command(&mut self, line: &str) -> anyhow::Result<bool> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&command, args)) = words.split_first() else {
+            return Ok(true);
+        };
+        let arg = |i: usize| -> anyhow::Result<&str> {
+            args.get(i)
+                .copied()
+                .with_context(|| format!("Missing argument of '{command}'"))
+        };
+        match command {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy"
+            | "computer" | "name" | "rating" | "otim" | "white" | "black" => {}
+            "protover" => println!(
+                "feature myname=\"{} {}\" usermove=1 setboard=1 ping=1 colors=0 \
+                 sigint=0 sigterm=0 done=1",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            ),
+            "new" => {
+                self.stop();
+                self.board = pleco::Board::start_pos();
+                self.tt.clear();
+                self.engine_side = Some(pleco::Player::Black);
+                self.max_depth = None;
+            }
+            "force" | "result" => {
+                self.stop();
+                self.engine_side = None;
+            }
+            "go" => {
+                self.stop();
+                self.engine_side = Some(self.board.turn());
+                self.start_search_if_engine_turn();
+            }
+            // Move now: the search returns its best move found so far
+            "?" => {
+                if let Some(search) = &self.search {
+                    search.cancellation.cancel();
+                }
+            }
+            "usermove" => self.user_move(arg(0)?),
+            "undo" => self.undo(1),
+            "remove" => self.undo(2),
+            "setboard" => {
+                self.stop();
+                let fen = args.join(" ");
+                match parse_fen(&fen) {
+                    Ok(board) => self.board = board,
+                    Err(e) => println!("tellusererror Illegal position: {e:#}"),
+                }
+            }
+            "sd" => self.max_depth = Some(arg(0)?.parse()?),
+            "st" => {
+                self.move_time = Some(Duration::from_secs(arg(0)?.parse()?));
+                self.level = None;
+            }
+            "level" => {
+                self.level = Some(Level {
+                    moves_per_control: arg(0)?.parse()?,
+                    increment: Duration::from_secs_f64(arg(2)?.parse()?),
+                });
+                self.remaining = Some(parse_base_time(arg(1)?)?);
+                self.move_time = None;
+            }
+            // Engine clock in centiseconds
+            "time" => {
+                self.remaining =
+                    Some(Duration::from_millis(arg(0)?.parse::<u64>()? * 10))
+            }
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => println!("pong {}", arg(0)?),
+            "quit" => {
+                self.stop();
+                return Ok(false);
+            }
+            // Moves without "usermove" prefix from interfaces which ignore the
+            // feature
+            _ if self
+                .board
+                .generate_moves()
+                .iter()
+                .any(|m| m.to_string() == command) =>
+            {
+                self.user_move(command)
+            }
+            _ => println!("Error (unknown command): {command}"),
+        }
+        Ok(true)
+    }
+}
+
+/// XBoard (CECP) protocol front-end for chess-alpha-beta. Reads commands from
+/// stdin and writes responses to stdout.
+#[derive(clap::Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {}
+
+fn main() -> anyhow::Result<()> {
+    use clap::Parser;
+    Args::parse();
+    let (sender, receiver) = mpsc::channel();
+    let stdin_sender = sender.clone();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if stdin_sender.send(Event::Line(line)).is_err() {
+                break;
+            }
+        }
+        let _ = stdin_sender.send(Event::Line("quit".to_string()));
+    });
+    let mut engine = Engine::new(sender);
+    for event in receiver {
+        match event {
+            Event::Line(line) => match engine.handle_command(&line) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => println!("Error ({e:#}): {line}"),
+            },
+            Event::SearchDone(id, result) => engine.on_search_done(id, result),
+        }
+    }
+    Ok(())
+}
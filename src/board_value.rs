@@ -1,27 +1,59 @@
 use crate::alpha_beta::ValueType;
 use pleco;
 
-pub fn board_value(board: &pleco::Board, depth: u32) -> ValueType {
-    let mut mate_score = ValueType::default();
+/// Value of a checkmate at the root. A mate found `ply` plies from the root is
+/// worth `MATE_VALUE - ply`, so faster mates are preferred.
+pub const MATE_VALUE: ValueType = 1_000_000;
+/// Values at least this far from 0 are mates
+pub const MATE_BOUND: ValueType = MATE_VALUE - 10_000;
+
+/// Game phase of the initial position
+pub const MAX_PHASE: ValueType = 24;
+/// Contribution of every piece to the game phase
+const PHASE_WEIGHTS: [(pleco::PieceType, ValueType); 4] = [
+    (pleco::PieceType::N, 1),
+    (pleco::PieceType::B, 1),
+    (pleco::PieceType::R, 2),
+    (pleco::PieceType::Q, 4),
+];
+
+/// Phase of the game by the pieces left on the board: `MAX_PHASE` with all
+/// pieces, 0 with only kings and pawns. Middlegame and endgame scores are
+/// mixed in this proportion, so a capture moves the score only a little.
+pub fn game_phase(board: &pleco::Board) -> ValueType {
+    let phase: ValueType = PHASE_WEIGHTS
+        .iter()
+        .map(|&(piece, weight)| {
+            weight
+                * ValueType::from(
+                    board.count_piece(pleco::Player::White, piece)
+                        + board.count_piece(pleco::Player::Black, piece),
+                )
+        })
+        .sum();
+    // Promotions can add pieces
+    phase.min(MAX_PHASE)
+}
+
+/// Value of `board` for White. `ply` is the distance from the root of the
+/// search and only counts for checkmates.
+pub fn board_value(board: &pleco::Board, ply: u32) -> ValueType {
     if board.checkmate() {
-        // The less moves to mate - the more mate bonus.
-        // depth at the start of an algorithm is equal to max_depth
-        // and decreases to the leaf nodes of the solution tree.
-        let moves_to_mate_bonus = depth as ValueType * 1.0e5 as ValueType;
-        if board.turn() == pleco::Player::Black {
-            mate_score = 1.0e6 as ValueType + moves_to_mate_bonus;
-        } else {
-            mate_score = -1.0e6 as ValueType - moves_to_mate_bonus;
-        }
+        let mate_score = MATE_VALUE - ply as ValueType;
+        return match board.turn() {
+            pleco::Player::White => -mate_score,
+            pleco::Player::Black => mate_score,
+        };
     }
-    (if std::cmp::min(
-        board.count_pieces_player(pleco::Player::White),
-        board.count_pieces_player(pleco::Player::Black),
-    ) < 9
-    {
-        board.psq().eg()
-    } else {
-        board.psq().mg()
-    } as ValueType
-        + mate_score)
+    let phase = game_phase(board);
+    let psq = board.psq();
+    (psq.mg() as ValueType * phase + psq.eg() as ValueType * (MAX_PHASE - phase))
+        / MAX_PHASE
+}
+
+/// Converts a `value` found by a search into the number of plies from the root
+/// to mate. The sign of the result is the sign of `value`. Returns None if the
+/// value is not a mate.
+pub fn plies_to_mate(value: ValueType) -> Option<i32> {
+    (value.abs() >= MATE_BOUND).then(|| value.signum() * (MATE_VALUE - value.abs()))
 }
@@ -1,3 +1,8 @@
+use crate::alpha_beta::SearchOptions;
+use crate::book::{BookSelection, OpeningBook};
+use crate::search_limits::{Clock, SearchLimits};
+use crate::transposition_table::TranspositionTable;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
 pub enum GameMode {
     /// Computer-Computer
@@ -6,21 +11,341 @@ pub enum GameMode {
     WUBC,
     /// Black User-White Computer
     BUWC,
+    /// User-User
+    UU,
+    /// Replay the game from --load-pgn move by move
+    Replay,
+    /// Match of --white against --black over --games games with alternating
+    /// colours
+    Match,
+    /// Match of candidate --white against baseline --black until a sequential
+    /// probability ratio test accepts --elo0 or --elo1
+    Sprt,
+}
+
+/// Search settings of an engine player which replace the ones of `Config`, so
+/// that two engine configurations can play each other
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EngineSpec {
+    pub depth: Option<std::num::NonZeroU32>,
+    pub nodes: Option<u64>,
+    pub move_time: Option<u64>,
+    pub clock: Option<u64>,
+    pub increment: Option<u64>,
+    pub hash_size: Option<usize>,
+    pub quiescence_checks: Option<bool>,
+    pub shuffle_moves: Option<bool>,
+    pub seed: Option<u64>,
+    pub book: Option<std::path::PathBuf>,
+    pub book_selection: Option<BookSelection>,
+    pub no_book: Option<bool>,
+}
+
+impl EngineSpec {
+    /// `config` with the settings given in this spec
+    pub fn apply(&self, config: &Config) -> Config {
+        Config {
+            depth: self.depth.unwrap_or(config.depth),
+            nodes: self.nodes.or(config.nodes),
+            move_time: self.move_time.or(config.move_time),
+            clock: self.clock.or(config.clock),
+            increment: self.increment.unwrap_or(config.increment),
+            hash_size: self.hash_size.unwrap_or(config.hash_size),
+            quiescence_checks: self
+                .quiescence_checks
+                .unwrap_or(config.quiescence_checks),
+            shuffle_moves: self.shuffle_moves.unwrap_or(config.shuffle_moves),
+            seed: self.seed.or(config.seed),
+            book: self.book.clone().or_else(|| config.book.clone()),
+            book_selection: self.book_selection.unwrap_or(config.book_selection),
+            no_book: self.no_book.unwrap_or(config.no_book),
+            ..config.clone()
+        }
+    }
+}
+
+fn parse_option<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("Invalid {key} '{value}': {e}"))
+}
+
+/// Flags are set without a value or with "true" or "false"
+fn parse_flag(key: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "" => Ok(true),
+        _ => parse_option(key, value),
+    }
+}
+
+impl std::str::FromStr for EngineSpec {
+    type Err = String;
+
+    /// Comma separated options named like the command line options, e.g.
+    /// "depth=8,nodes=100000,quiescence-checks", or only a depth
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spec = Self::default();
+        if !s.is_empty() && s.chars().all(|x| x.is_ascii_digit()) {
+            spec.depth = Some(parse_option("depth", s)?);
+            return Ok(spec);
+        }
+        for option in s.split(',').filter(|x| !x.is_empty()) {
+            let (key, value) = option.split_once('=').unwrap_or((option, ""));
+            match key {
+                "depth" => spec.depth = Some(parse_option(key, value)?),
+                "nodes" => spec.nodes = Some(parse_option(key, value)?),
+                "move-time" => spec.move_time = Some(parse_option(key, value)?),
+                "clock" => spec.clock = Some(parse_option(key, value)?),
+                "increment" => spec.increment = Some(parse_option(key, value)?),
+                "hash-size" => spec.hash_size = Some(parse_option(key, value)?),
+                "quiescence-checks" => {
+                    spec.quiescence_checks = Some(parse_flag(key, value)?)
+                }
+                "shuffle-moves" => {
+                    spec.shuffle_moves = Some(parse_flag(key, value)?)
+                }
+                "seed" => spec.seed = Some(parse_option(key, value)?),
+                "book" if !value.is_empty() => spec.book = Some(value.into()),
+                "book-selection" => {
+                    spec.book_selection = Some(
+                        clap::ValueEnum::from_str(value, true)
+                            .map_err(|e| format!("Invalid {key} '{value}': {e}"))?,
+                    )
+                }
+                "no-book" => spec.no_book = Some(parse_flag(key, value)?),
+                _ => return Err(format!("Invalid engine option '{option}'")),
+            }
+        }
+        Ok(spec)
+    }
+}
+
+impl std::fmt::Display for EngineSpec {
+    /// Options in the format they are parsed from
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let options = [
+            ("depth", self.depth.map(|x| x.to_string())),
+            ("nodes", self.nodes.map(|x| x.to_string())),
+            ("move-time", self.move_time.map(|x| x.to_string())),
+            ("clock", self.clock.map(|x| x.to_string())),
+            ("increment", self.increment.map(|x| x.to_string())),
+            ("hash-size", self.hash_size.map(|x| x.to_string())),
+            (
+                "quiescence-checks",
+                self.quiescence_checks.map(|x| x.to_string()),
+            ),
+            ("shuffle-moves", self.shuffle_moves.map(|x| x.to_string())),
+            ("seed", self.seed.map(|x| x.to_string())),
+            ("book", self.book.as_ref().map(|x| x.display().to_string())),
+            (
+                "book-selection",
+                self.book_selection
+                    .and_then(|x| clap::ValueEnum::to_possible_value(&x))
+                    .map(|x| x.get_name().to_string()),
+            ),
+            ("no-book", self.no_book.map(|x| x.to_string())),
+        ];
+        let options: Vec<String> = options
+            .into_iter()
+            .filter_map(|(key, value)| Some(format!("{key}={}", value?)))
+            .collect();
+        f.write_str(&options.join(","))
+    }
+}
+
+/// Player of one side replacing the one from the game mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayerSpec {
+    User,
+    /// Engine with its own settings if given
+    Engine(EngineSpec),
+    /// Moves from a file
+    Script(std::path::PathBuf),
+    /// UCI engine at "host:port"
+    Remote(String),
+}
+
+impl std::str::FromStr for PlayerSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, arg) = s.split_once(':').unwrap_or((s, ""));
+        match (kind, arg) {
+            ("user", "") => Ok(Self::User),
+            ("engine", options) => options.parse().map(Self::Engine),
+            ("script", path) if !path.is_empty() => Ok(Self::Script(path.into())),
+            ("remote", address) if !address.is_empty() => {
+                Ok(Self::Remote(address.to_string()))
+            }
+            _ => Err(format!(
+                "Expected user, engine[:OPTIONS], script:FILE or remote:HOST:PORT, got '{s}'"
+            )),
+        }
+    }
 }
 
 /// Chess solving program based on minimax algorithm with alpha-beta pruning optimization
-#[derive(Debug, clap::Parser)]
+#[derive(Debug, Clone, clap::Parser)]
 #[command(version, about, long_about = None)]
 pub struct Config {
     /// Depth of a search tree
     #[arg(short, long, default_value = "6")]
     pub depth: std::num::NonZeroU32,
 
+    /// Maximum number of nodes searched per move
+    #[arg(long)]
+    pub nodes: Option<u64>,
+
+    /// Time to search per move in milliseconds
+    #[arg(long)]
+    pub move_time: Option<u64>,
+
+    /// Initial time on the clock of every computer player in milliseconds
+    #[arg(long)]
+    pub clock: Option<u64>,
+
+    /// Time added to the computer clock after every move in milliseconds
+    #[arg(long, default_value = "0")]
+    pub increment: u64,
+
     /// Show user's potentially best move when playing with computer
     #[arg(short, long)]
     pub evaluate_user: bool,
 
+    /// Size of the transposition table in megabytes
+    #[arg(long, default_value_t = TranspositionTable::DEFAULT_SIZE_MB)]
+    pub hash_size: usize,
+
+    /// Also search quiet checking moves in quiescence search
+    #[arg(long)]
+    pub quiescence_checks: bool,
+
+    /// Randomly break ties between equally ordered moves
+    #[arg(long)]
+    pub shuffle_moves: bool,
+
+    /// Make the search deterministic: search root moves on a single thread and
+    /// shuffle moves with this seed if --shuffle-moves is given
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Opening book in Polyglot format, e.g. written by chess-alpha-beta-book.
+    /// The computer plays book moves without searching.
+    #[arg(long)]
+    pub book: Option<std::path::PathBuf>,
+
+    /// How the computer picks one of the book moves
+    #[arg(long, default_value = "weighted")]
+    pub book_selection: BookSelection,
+
+    /// Don't use the opening book even if --book is given
+    #[arg(long)]
+    pub no_book: bool,
+
+    /// Save the game to this PGN file after every move
+    #[arg(long)]
+    pub pgn: Option<std::path::PathBuf>,
+
+    /// Continue the game from this PGN file. Replay mode shows its moves.
+    #[arg(long, conflicts_with_all = ["fen", "fen_file"])]
+    pub load_pgn: Option<std::path::PathBuf>,
+
+    /// Start the game from this position
+    #[arg(long, conflicts_with = "fen_file")]
+    pub fen: Option<String>,
+
+    /// Start the game from a position of this file with one FEN per line
+    #[arg(long)]
+    pub fen_file: Option<std::path::PathBuf>,
+
+    /// Number of the game in the --load-pgn file or of the position in the
+    /// --fen-file, starting from 1
+    #[arg(long, default_value = "1")]
+    pub game: std::num::NonZeroUsize,
+
+    /// White player instead of the one from the game mode: user, engine[:OPTIONS],
+    /// script:FILE with moves or remote:HOST:PORT of a UCI engine. Engine options
+    /// replace the search options above, e.g. engine:depth=8,nodes=100000 or
+    /// engine:8.
+    #[arg(long)]
+    pub white: Option<PlayerSpec>,
+
+    /// Black player instead of the one from the game mode, see --white
+    #[arg(long)]
+    pub black: Option<PlayerSpec>,
+
+    /// Number of games in match mode, 2 by default, or maximum number of games
+    /// in sprt mode. Every opening position is played twice with swapped colours.
+    #[arg(long)]
+    pub games: Option<std::num::NonZeroUsize>,
+
+    /// Elo difference of the null hypothesis in sprt mode
+    #[arg(long, default_value = "0", allow_negative_numbers = true)]
+    pub elo0: f64,
+
+    /// Elo difference of the alternative hypothesis in sprt mode
+    #[arg(long, default_value = "5", allow_negative_numbers = true)]
+    pub elo1: f64,
+
+    /// Probability to accept the alternative hypothesis when the null one is
+    /// true in sprt mode
+    #[arg(long, default_value = "0.05")]
+    pub alpha: f64,
+
+    /// Probability to accept the null hypothesis when the alternative one is
+    /// true in sprt mode
+    #[arg(long, default_value = "0.05")]
+    pub beta: f64,
+
+    /// Number of games played at the same time in sprt mode, the number of
+    /// CPUs by default
+    #[arg(long)]
+    pub concurrency: Option<std::num::NonZeroUsize>,
+
+    /// Don't print boards and searches of computer players and how the game ended
+    #[arg(short, long)]
+    pub quiet: bool,
+
     /// Game mode
     #[arg(short, long)]
     pub mode: GameMode,
 }
+
+impl Config {
+    pub fn initial_clock(&self) -> Option<Clock> {
+        self.clock.map(|x| Clock {
+            remaining: std::time::Duration::from_millis(x),
+            increment: std::time::Duration::from_millis(self.increment),
+            moves_to_go: None,
+        })
+    }
+
+    pub fn search_limits(&self, clock: Option<Clock>) -> SearchLimits {
+        SearchLimits {
+            max_depth: self.depth,
+            max_nodes: self.nodes,
+            move_time: self.move_time.map(std::time::Duration::from_millis),
+            clock,
+            cancellation: None,
+        }
+    }
+
+    /// The --book unless --no-book is given
+    pub fn opening_book(&self) -> anyhow::Result<Option<OpeningBook>> {
+        match &self.book {
+            Some(path) if !self.no_book => OpeningBook::open(path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn search_options(&self) -> SearchOptions {
+        SearchOptions {
+            quiescence_checks: self.quiescence_checks,
+            shuffle_moves: self.shuffle_moves,
+            seed: self.seed,
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::transposition_table::position_key;
+use pleco::PieceType;
+
+/// Why a game ended in a draw other than stalemate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DrawReason {
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+}
+
+impl std::fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DrawReason::ThreefoldRepetition => "threefold repetition",
+            DrawReason::FiftyMoveRule => "fifty-move rule",
+            DrawReason::InsufficientMaterial => "insufficient material",
+        })
+    }
+}
+
+/// Zobrist keys of the positions played before `board`, oldest first. Positions
+/// before the last capture or pawn move can never repeat and are left out.
+///
+/// Only moves played on this board or its `parallel_clone` are known: `clone`
+/// forgets how many moves can be undone.
+pub fn position_history(board: &pleco::Board) -> Vec<u64> {
+    let len = u16::try_from(board.rule_50())
+        .unwrap_or(0)
+        .min(board.depth());
+    let mut experiment_board = board.parallel_clone();
+    let mut keys = Vec::with_capacity(len.into());
+    for _ in 0..len {
+        experiment_board.undo_move();
+        keys.push(position_key(&experiment_board));
+    }
+    keys.reverse();
+    keys
+}
+
+/// Number of times the position of `board` occurred in `history`, the keys of
+/// the positions played before it
+pub fn repetitions(board: &pleco::Board, history: &[u64]) -> usize {
+    let key = position_key(board);
+    history
+        .iter()
+        .rev()
+        .take(board.rule_50().max(0) as usize)
+        // Only positions with the same side to move can be equal
+        .skip(1)
+        .step_by(2)
+        .filter(|&&x| x == key)
+        .count()
+}
+
+/// Neither side can mate: bare kings, a single minor piece or only bishops on
+/// squares of one color
+pub fn insufficient_material(board: &pleco::Board) -> bool {
+    if board
+        .piece_two_bb_both_players(PieceType::P, PieceType::R)
+        .is_not_empty()
+        || board.piece_bb_both_players(PieceType::Q).is_not_empty()
+    {
+        return false;
+    }
+    let knights = board.piece_bb_both_players(PieceType::N);
+    let bishops = board.piece_bb_both_players(PieceType::B);
+    let minors = knights.count_bits() + bishops.count_bits();
+    minors <= 1
+        || knights.is_empty() && {
+            let mut colors = bishops.map(|sq| sq.on_light_square());
+            let first = colors.next();
+            colors.all(|x| Some(x) == first)
+        }
+}
+
+/// Returns the reason if the game on `board` is drawn by the rules. Stalemate
+/// is not included.
+pub fn draw_reason(board: &pleco::Board) -> Option<DrawReason> {
+    if board.rule_50() >= 100 && !board.checkmate() {
+        Some(DrawReason::FiftyMoveRule)
+    } else if repetitions(board, &position_history(board)) >= 2 {
+        Some(DrawReason::ThreefoldRepetition)
+    } else if insufficient_material(board) {
+        Some(DrawReason::InsufficientMaterial)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,224 @@
+use crate::config::{Config, EngineSpec, PlayerSpec};
+use crate::fen::parse_fen;
+use crate::game_modes::{GameRecord, make_player, play_game};
+use crate::pgn::{game_result, parse_pgn};
+use crate::player::Player;
+use anyhow::Context;
+use std::io::Write;
+
+/// Results of a match from the point of view of the first player
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// Elo difference of a player with expected `score` in (0, 1)
+fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Expected score of a player `elo` points stronger than the opponent
+fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+impl MatchScore {
+    /// Counts a finished game with PGN `result`
+    pub fn add(&mut self, result: &str, first_is_white: bool) {
+        match (result, first_is_white) {
+            ("1-0", true) | ("0-1", false) => self.wins += 1,
+            ("1-0", false) | ("0-1", true) => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, a draw is half a point
+    pub fn score(&self) -> f64 {
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0)
+            / f64::from(self.games())
+    }
+
+    /// Elo difference estimated from the score. Infinite without lost or won
+    /// games.
+    pub fn elo_difference(&self) -> f64 {
+        elo_from_score(self.score())
+    }
+
+    /// Variance of the points of one game
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        (f64::from(self.wins) * (1.0 - score).powi(2)
+            + f64::from(self.draws) * (0.5 - score).powi(2)
+            + f64::from(self.losses) * score.powi(2))
+            / f64::from(self.games())
+    }
+
+    /// Log-likelihood ratio of Elo difference `elo1` against `elo0` with the
+    /// score approximated by a normal distribution. 0 until the results differ.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance() / f64::from(self.games());
+        if variance.is_nan() || variance == 0.0 {
+            return 0.0;
+        }
+        let score0 = score_from_elo(elo0);
+        let score1 = score_from_elo(elo1);
+        (score1 - score0) * (2.0 * self.score() - score0 - score1) / (2.0 * variance)
+    }
+
+    /// Half of the 95% confidence interval of the Elo difference
+    pub fn elo_error(&self) -> f64 {
+        if !self.elo_difference().is_finite() {
+            return f64::INFINITY;
+        }
+        let score = self.score();
+        let margin = 1.96 * (self.variance() / f64::from(self.games())).sqrt();
+        let low = (score - margin).max(0.0);
+        let high = (score + margin).min(1.0);
+        (elo_from_score(high) - elo_from_score(low)) / 2.0
+    }
+}
+
+impl std::fmt::Display for MatchScore {
+    /// "W - L - D [score%] games, Elo difference: X +/- Y"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} - {} - {} [{:.1}%] {}, Elo difference: {:.1} +/- {:.1}",
+            self.wins,
+            self.losses,
+            self.draws,
+            self.score() * 100.0,
+            self.games(),
+            self.elo_difference(),
+            self.elo_error()
+        )
+    }
+}
+
+/// Positions the games of a match start from: all games of the `--load-pgn`
+/// file, all positions of the `--fen-file`, the `--fen` position or the initial
+/// position
+pub fn opening_boards(config: &Config) -> anyhow::Result<Vec<pleco::Board>> {
+    let boards = if let Some(path) = &config.load_pgn {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        parse_pgn(&text)
+            .and_then(|games| games.iter().map(|x| x.replay()).collect())
+            .with_context(|| format!("Failed to parse '{}'", path.display()))?
+    } else if let Some(path) = &config.fen_file {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        text.lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(parse_fen)
+            .collect::<anyhow::Result<_>>()?
+    } else if let Some(fen) = &config.fen {
+        vec![parse_fen(fen)?]
+    } else {
+        vec![pleco::Board::start_pos()]
+    };
+    anyhow::ensure!(!boards.is_empty(), "No opening positions");
+    Ok(boards)
+}
+
+/// Creates the first and the second player of a match from `--white` and
+/// `--black`, engines by default
+pub fn match_players(
+    config: &Config,
+) -> anyhow::Result<(Box<dyn Player>, Box<dyn Player>)> {
+    let engine = PlayerSpec::Engine(EngineSpec::default());
+    let first = make_player(config.white.as_ref().unwrap_or(&engine), config)
+        .context("Failed to create first player")?;
+    let second = make_player(config.black.as_ref().unwrap_or(&engine), config)
+        .context("Failed to create second player")?;
+    Ok((first, second))
+}
+
+/// Plays game number `game` of a match from 0. Every opening is played twice,
+/// the first player takes White in even games. Returns the final position, the
+/// record of the game and whether the first player was White.
+pub fn play_match_game(
+    config: &Config,
+    openings: &[pleco::Board],
+    game: usize,
+    first: &mut dyn Player,
+    second: &mut dyn Player,
+) -> anyhow::Result<(pleco::Board, GameRecord, bool)> {
+    first.new_game()?;
+    second.new_game()?;
+    let opening = openings[game / 2 % openings.len()].parallel_clone();
+    let first_is_white = game.is_multiple_of(2);
+    let (board, mut record) = if first_is_white {
+        play_game(config, opening, first, second)
+    } else {
+        play_game(config, opening, second, first)
+    };
+    anyhow::ensure!(
+        game_result(&board) != "*",
+        "Game {} was not finished",
+        game + 1
+    );
+    record.headers.event = "chess-alpha-beta match".to_string();
+    record.headers.round = (game + 1).to_string();
+    Ok((board, record, first_is_white))
+}
+
+/// Appends a game to a PGN file
+pub fn append_pgn(path: &std::path::Path, pgn: &str) -> anyhow::Result<()> {
+    std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .and_then(|mut x| writeln!(x, "{pgn}"))
+        .with_context(|| format!("Failed to write '{}'", path.display()))
+}
+
+/// Plays `config.games` games between `--white` and `--black` players. All
+/// games are written to the `--pgn` file.
+pub fn run_match(config: Config) -> anyhow::Result<MatchScore> {
+    let openings = opening_boards(&config)?;
+    // Games are written once they are over
+    let game_config = Config {
+        pgn: None,
+        ..config.clone()
+    };
+    let (mut first, mut second) = match_players(&game_config)?;
+    let (first_name, second_name) = (first.name(), second.name());
+    if let Some(path) = &config.pgn {
+        std::fs::write(path, "")
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    }
+
+    let games = config.games.map_or(2, std::num::NonZeroUsize::get);
+    let mut score = MatchScore::default();
+    for game in 0..games {
+        let (board, record, first_is_white) = play_match_game(
+            &game_config,
+            &openings,
+            game,
+            first.as_mut(),
+            second.as_mut(),
+        )?;
+        let result = game_result(&board);
+        score.add(result, first_is_white);
+        if let Some(path) = &config.pgn {
+            append_pgn(path, &record.pgn(&board))?;
+        }
+        println!(
+            "Game {} of {}: {} vs {}: {}",
+            game + 1,
+            games,
+            record.headers.white,
+            record.headers.black,
+            result
+        );
+        println!("Score of {} vs {}: {}", first_name, second_name, score);
+    }
+    Ok(score)
+}
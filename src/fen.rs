@@ -0,0 +1,95 @@
+use anyhow::Context;
+
+/// Larger move numbers overflow the move counters of pleco during a game
+const MAX_FULLMOVE_NUMBER: u16 = 16000;
+
+/// Parses a position in Forsyth-Edwards Notation. Unlike `Board::from_fen`
+/// it never panics and rejects positions which cannot occur in a game:
+/// wrong number of kings, castling rights without the king and rook on their
+/// squares or the side which has just moved left in check. The move counters
+/// may be left out but must be numbers if present.
+pub fn parse_fen(fen: &str) -> anyhow::Result<pleco::Board> {
+    let fen = fen.trim();
+    validated_board(fen).with_context(|| format!("Invalid FEN '{fen}'"))
+}
+
+fn validated_board(fen: &str) -> anyhow::Result<pleco::Board> {
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    anyhow::ensure!(
+        (4..=6).contains(&fields.len()),
+        "FEN must have 4 to 6 fields, found {}",
+        fields.len()
+    );
+
+    // Piece placement from rank 8 to rank 1
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    anyhow::ensure!(
+        ranks.len() == 8,
+        "FEN must have 8 ranks, found {}",
+        ranks.len()
+    );
+    let mut squares = [[' '; 8]; 8];
+    for (i, rank) in ranks.iter().enumerate() {
+        let mut file = 0;
+        for c in rank.chars() {
+            if let Some(empty) = c.to_digit(10).filter(|x| (1..=8).contains(x)) {
+                file += empty as usize;
+            } else {
+                anyhow::ensure!("pnbrqkPNBRQK".contains(c), "Invalid piece '{c}'");
+                if let Some(square) = squares[7 - i].get_mut(file) {
+                    *square = c;
+                }
+                file += 1;
+            }
+        }
+        anyhow::ensure!(file == 8, "Rank {} has {} squares", 8 - i, file);
+    }
+    for king in ['K', 'k'] {
+        let count = squares.iter().flatten().filter(|&&x| x == king).count();
+        anyhow::ensure!(count == 1, "Expected one '{king}', found {count}");
+    }
+    if let Some(&castling) = fields.get(2)
+        && castling != "-"
+    {
+        for right in castling.chars() {
+            let (king, rook, rank, rook_file) = match right {
+                'K' => ('K', 'R', 0, 7),
+                'Q' => ('K', 'R', 0, 0),
+                'k' => ('k', 'r', 7, 7),
+                'q' => ('k', 'r', 7, 0),
+                _ => anyhow::bail!("Invalid castling right '{right}'"),
+            };
+            anyhow::ensure!(
+                squares[rank][4] == king && squares[rank][rook_file] == rook,
+                "Castling right '{right}' without the king and rook on their squares"
+            );
+        }
+    }
+    if let Some(&halfmove) = fields.get(4) {
+        anyhow::ensure!(
+            halfmove
+                .parse::<u16>()
+                .is_ok_and(|x| x <= 2 * MAX_FULLMOVE_NUMBER),
+            "Invalid halfmove clock '{halfmove}'"
+        );
+    }
+    if let Some(&fullmove) = fields.get(5) {
+        anyhow::ensure!(
+            fullmove
+                .parse::<u16>()
+                .is_ok_and(|x| (1..=MAX_FULLMOVE_NUMBER).contains(&x)),
+            "Invalid fullmove number '{fullmove}', expected 1 to {MAX_FULLMOVE_NUMBER}"
+        );
+    }
+
+    let board = pleco::Board::from_fen(fen)
+        .map_err(|e| anyhow::anyhow!("{}", format!("{e:?}").trim()))?;
+    let them = !board.turn();
+    anyhow::ensure!(
+        (board.attackers_to(board.king_sq(them), board.occupied())
+            & board.get_occupied_player(board.turn()))
+        .is_empty(),
+        "Side not to move is in check"
+    );
+    Ok(board)
+}
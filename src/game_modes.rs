@@ -1,242 +1,289 @@
-use crate::alpha_beta::get_best_move;
 use crate::board_pretty_print::board_pretty_print;
-use crate::config::Config;
-use std::io::Write;
+use crate::config::{Config, EngineSpec, GameMode, PlayerSpec};
+use crate::draw::draw_reason;
+use crate::fen::parse_fen;
+use crate::pgn::{
+    MoveComment, PgnGame, PgnHeaders, game_result, parse_pgn, played_moves,
+    write_pgn,
+};
+use crate::player::{
+    EnginePlayer, HumanPlayer, Player, RemotePlayer, ScriptedPlayer, Turn, input,
+    pv_to_string, search_best_move,
+};
+use crate::san::move_to_san;
+use crate::transposition_table::TranspositionTable;
+use anyhow::Context;
 
-fn input(promt: &str) -> String {
-    let mut ret = String::new();
-    print!("{}", promt);
-    std::io::stdout().flush().expect("flush failed");
-    std::io::stdin()
-        .read_line(&mut ret)
-        .expect("Failed to read line");
-    ret.trim().to_string()
+/// Moves of the current game with engine evaluations for PGN export
+pub struct GameRecord {
+    pub headers: PgnHeaders,
+    /// Comments for the moves played on the game board
+    comments: Vec<Option<MoveComment>>,
 }
 
-enum UserCommand {
-    MakeMove(String),
-    Undo,
-    ChangeDepth(std::num::NonZeroU32),
-    ChangeEvaluateUser(bool),
-}
-
-fn input_user_command(promt: &str) -> Option<UserCommand> {
-    let s = input(promt);
-    let bytes = s.as_bytes();
-    let mut i = 0;
-    let c = bytes[i];
-    if c == b'd' {
-        i += 1;
-        while i < s.len() && bytes[i].is_ascii_whitespace() {
-            i += 1;
-            continue;
+impl GameRecord {
+    pub fn new(white: &str, black: &str) -> Self {
+        Self {
+            headers: PgnHeaders {
+                event: "chess-alpha-beta game".to_string(),
+                white: white.to_string(),
+                black: black.to_string(),
+                ..Default::default()
+            },
+            comments: Vec::new(),
         }
-        if i < s.len() && bytes[i].is_ascii_digit() {
-            let mut depth = String::from(char::from(bytes[i]));
-            i += 1;
-            while i < s.len() && bytes[i].is_ascii_digit() {
-                depth.push(char::from(bytes[i]));
-                i += 1;
-            }
-            if i == s.len() {
-                return Some(UserCommand::ChangeDepth(
-                    depth.parse::<std::num::NonZeroU32>().ok()?,
-                ));
-            }
+    }
+
+    /// Records `comment` for the last move played on `game_board`. Comments of
+    /// undone moves are dropped.
+    fn push(&mut self, game_board: &pleco::Board, comment: Option<MoveComment>) {
+        self.comments
+            .resize(usize::from(game_board.depth()).saturating_sub(1), None);
+        self.comments.push(comment);
+    }
+
+    /// The game played on `game_board` in PGN
+    pub fn pgn(&self, game_board: &pleco::Board) -> String {
+        write_pgn(game_board, &self.headers, &self.comments)
+    }
+
+    fn save(
+        &self,
+        game_board: &pleco::Board,
+        path: &std::path::Path,
+    ) -> anyhow::Result<()> {
+        std::fs::write(path, self.pgn(game_board))
+            .with_context(|| format!("Failed to write '{}'", path.display()))
+    }
+
+    /// Saves the game to the PGN file from the config if there is one
+    fn autosave(&self, game_board: &pleco::Board, config: &Config) {
+        if let Some(path) = &config.pgn
+            && let Err(e) = self.save(game_board, path)
+        {
+            println!("{e:#}");
         }
-    } else if c == b'e' {
-        i += 1;
-        while i < s.len() && bytes[i].is_ascii_whitespace() {
-            i += 1;
-            continue;
+    }
+}
+
+/// Prints the result and returns true if the loaded game is already over
+fn game_is_over(game_board: &pleco::Board) -> bool {
+    let result = game_result(game_board);
+    if result != "*" {
+        board_pretty_print(game_board);
+        println!("Game is over: {}", result);
+    }
+    result != "*"
+}
+
+/// Reads game number `config.game` from the `--load-pgn` file
+fn load_game(config: &Config) -> anyhow::Result<Option<PgnGame>> {
+    let Some(path) = &config.load_pgn else {
+        return Ok(None);
+    };
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read '{}'", path.display()))?;
+    let mut games = parse_pgn(&text)
+        .with_context(|| format!("Failed to parse '{}'", path.display()))?;
+    let count = games.len();
+    anyhow::ensure!(
+        config.game.get() <= count,
+        "'{}' has only {} games",
+        path.display(),
+        count
+    );
+    Ok(Some(games.swap_remove(config.game.get() - 1)))
+}
+
+/// Reads position number `config.game` from the `--fen-file`
+fn load_fen(config: &Config) -> anyhow::Result<Option<String>> {
+    let Some(path) = &config.fen_file else {
+        return Ok(config.fen.clone());
+    };
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read '{}'", path.display()))?;
+    let fen = text
+        .lines()
+        .filter(|x| !x.trim().is_empty())
+        .nth(config.game.get() - 1)
+        .with_context(|| {
+            format!(
+                "'{}' has less than {} positions",
+                path.display(),
+                config.game
+            )
+        })?;
+    Ok(Some(fen.to_string()))
+}
+
+/// Board to play from: the final position of the loaded game with its moves,
+/// the position from `--fen` or `--fen-file` or the initial position
+pub fn initial_board(config: &Config) -> anyhow::Result<pleco::Board> {
+    if let Some(game) = load_game(config)? {
+        return game.replay();
+    }
+    match load_fen(config)? {
+        Some(fen) => parse_fen(&fen),
+        None => Ok(pleco::Board::default()),
+    }
+}
+
+/// Shows the moves played on `game_board` one by one from the start of the
+/// game. Every position is evaluated if `config.evaluate_user` is set.
+pub fn replay(config: Config, game_board: pleco::Board) {
+    let tt = TranspositionTable::new(config.hash_size);
+    let (mut board, moves) = played_moves(&game_board);
+    board_pretty_print(&board);
+    for m in moves {
+        if config.evaluate_user {
+            let best = search_best_move(&board, &config, &tt, None);
+            println!(
+                "Best move = {}, value = {}, pv = {}",
+                move_to_san(&board, best.m),
+                best.value,
+                pv_to_string(&board, &best.pv)
+            );
         }
-        if i < s.len() {
-            if bytes[i] == b'0' {
-                return Some(UserCommand::ChangeEvaluateUser(false));
-            } else if bytes[i] == b'1' {
-                return Some(UserCommand::ChangeEvaluateUser(true));
-            }
+        if input("Press Enter for the next move or q to quit: ")
+            .map_or(true, |x| x == "q")
+        {
+            return;
         }
-    } else if c == b'u' {
-        return Some(UserCommand::Undo);
+        println!(
+            "{}{} {}",
+            board.moves_played() / 2 + 1,
+            if board.turn() == pleco::Player::White {
+                "."
+            } else {
+                "..."
+            },
+            move_to_san(&board, m)
+        );
+        board.apply_move(m);
+        board_pretty_print(&board);
     }
-    Some(UserCommand::MakeMove(s))
+    println!("Result: {}", game_result(&board));
+}
+
+pub fn make_player(
+    spec: &PlayerSpec,
+    config: &Config,
+) -> anyhow::Result<Box<dyn Player>> {
+    Ok(match spec {
+        PlayerSpec::User => Box::new(HumanPlayer::new(config.clone())),
+        PlayerSpec::Engine(spec) => Box::new(EnginePlayer::with_spec(config, spec)?),
+        PlayerSpec::Script(path) => Box::new(ScriptedPlayer::from_file(path)?),
+        PlayerSpec::Remote(address) => {
+            Box::new(RemotePlayer::connect(address, config.clone())?)
+        }
+    })
 }
 
-fn handle_user_move(game_board: &mut pleco::Board, config: &mut Config) {
+/// Plays a game from `game_board` until it ends or a player fails and returns
+/// the final position with the record of the game. Moves are saved to the
+/// `--pgn` file as they are played.
+pub fn play_game<'a>(
+    config: &Config,
+    mut game_board: pleco::Board,
+    white: &mut (dyn Player + 'a),
+    black: &mut (dyn Player + 'a),
+) -> (pleco::Board, GameRecord) {
+    let mut record = GameRecord::new(&white.name(), &black.name());
+    if game_is_over(&game_board) {
+        return (game_board, record);
+    }
     loop {
-        let user_move = input_user_command(&format!(
-            "Type {} move: ",
-            if game_board.turn() == pleco::Player::White {
-                "white"
-            } else {
-                "black"
-            }
-        ));
-        if user_move.is_none() {
-            println!("Invalid command. Try again.");
-            continue;
+        let (side, player) = match game_board.turn() {
+            pleco::Player::White => ("White", &mut *white),
+            pleco::Player::Black => ("Black", &mut *black),
+        };
+        if player.is_human()
+            || (!config.quiet && game_board.turn() == pleco::Player::White)
+        {
+            board_pretty_print(&game_board);
         }
-        match user_move.unwrap() {
-            UserCommand::Undo => {
-                if game_board.ply() >= 2 {
+        let turn = match player.play(&game_board) {
+            Ok(x) => x,
+            Err(e) => {
+                if !config.quiet {
+                    println!("{} player failed: {:#}", side, e);
+                }
+                break;
+            }
+        };
+        match turn {
+            Turn::Move(m, comment) => {
+                game_board.apply_move(m);
+                record.push(&game_board, comment);
+                record.autosave(&game_board, config);
+            }
+            Turn::Undo => {
+                if game_board.depth() >= 2 {
                     println!("Undoing...");
                     game_board.undo_move();
                     game_board.undo_move();
-                    board_pretty_print(game_board);
-                    continue;
+                    record.autosave(&game_board, config);
                 } else {
                     println!("Cannot be undone");
-                    continue;
                 }
+                continue;
             }
-            UserCommand::MakeMove(user_move) => {
-                let b = game_board.apply_uci_move(&user_move);
-                if !b {
-                    println!("Invalid move. try again.");
-                    continue;
+            Turn::SavePgn(path) => {
+                match path.map(Into::into).or_else(|| config.pgn.clone()) {
+                    Some(path) => match record.save(&game_board, &path) {
+                        Ok(()) => println!("Saved to {}", path.display()),
+                        Err(e) => println!("{e:#}"),
+                    },
+                    None => println!("PGN file is not given"),
                 }
-                break;
-            }
-            UserCommand::ChangeDepth(d) => {
-                println!("depth = {}", d);
-                config.depth = d;
                 continue;
             }
-            UserCommand::ChangeEvaluateUser(e) => {
-                println!("evaluate_user = {}", e);
-                config.evaluate_user = e;
+            Turn::Configure(setting) => {
+                white.configure(setting);
+                black.configure(setting);
                 continue;
             }
         }
-    }
-}
-
-pub fn computer_with_computer(config: Config) {
-    let mut game_board = pleco::Board::default();
-    loop {
-        board_pretty_print(&game_board);
-
-        let white_best = get_best_move(&game_board, config.depth).unwrap();
-        println!(
-            "White move = {}, value = {}",
-            white_best.m, white_best.value
-        );
-        game_board.apply_move(white_best.m);
-        if game_board.checkmate() {
-            println!("Chechmate! White won!");
-            break;
-        } else if game_board.stalemate() {
-            println!("Stalemate! Game over.");
-            break;
-        }
-
-        let black_best = get_best_move(&game_board, config.depth).unwrap();
-        println!(
-            "black move = {}, value = {}",
-            black_best.m, black_best.value
-        );
-        game_board.apply_move(black_best.m);
-        if game_board.checkmate() {
-            println!("Chechmate! Black won!");
-            break;
-        } else if game_board.stalemate() {
-            println!("Stalemate! Game over.");
-            break;
-        }
-    }
-}
-
-pub fn white_user_with_black_computer(mut config: Config) {
-    let mut game_board = pleco::Board::default();
-    loop {
-        board_pretty_print(&game_board);
-        let mut legal_moves: Vec<String> = game_board
-            .generate_moves()
-            .iter()
-            .map(|x| x.to_string())
-            .collect();
-        legal_moves.sort();
-        println!(
-            "legal_moves = {:?}, len = {}",
-            legal_moves,
-            legal_moves.len()
-        );
-        if config.evaluate_user {
-            let white_best = get_best_move(&game_board, config.depth).unwrap();
-            println!(
-                "White best move = {}, value = {}",
-                white_best.m, white_best.value
-            );
-        }
-        handle_user_move(&mut game_board, &mut config);
-        if game_board.checkmate() {
-            println!("Chechmate! White won!");
-            break;
+        let game_over = if game_board.checkmate() {
+            Some(format!("Chechmate! {} won!", side))
         } else if game_board.stalemate() {
-            println!("Stalemate! Game over.");
-            break;
-        }
-
-        let black_best = get_best_move(&game_board, config.depth).unwrap();
-        println!(
-            "black move = {}, value = {}",
-            black_best.m, black_best.value
-        );
-        game_board.apply_move(black_best.m);
-        if game_board.checkmate() {
-            println!("Chechmate! Black won!");
-            break;
-        } else if game_board.stalemate() {
-            println!("Stalemate! Game over.");
+            Some("Stalemate! Game over.".to_string())
+        } else {
+            draw_reason(&game_board).map(|x| format!("Draw by {}! Game over.", x))
+        };
+        if let Some(message) = game_over {
+            if !config.quiet {
+                println!("{message}");
+            }
             break;
         }
     }
+    (game_board, record)
 }
 
-pub fn black_user_with_white_computer(mut config: Config) {
-    let mut game_board = pleco::Board::default();
-    loop {
-        let white_best = get_best_move(&game_board, config.depth).unwrap();
-        println!(
-            "white move = {}, value = {}",
-            white_best.m, white_best.value
-        );
-        game_board.apply_move(white_best.m);
-        board_pretty_print(&game_board);
-        if game_board.checkmate() {
-            println!("Chechmate! White won!");
-            break;
-        } else if game_board.stalemate() {
-            println!("Stalemate! Game over.");
-            break;
+/// Plays a game between the players of the game mode, replaced by `--white`
+/// and `--black` if given
+pub fn play(config: Config, game_board: pleco::Board) -> anyhow::Result<()> {
+    let (white, black) = match config.mode {
+        GameMode::CC => (
+            PlayerSpec::Engine(EngineSpec::default()),
+            PlayerSpec::Engine(EngineSpec::default()),
+        ),
+        GameMode::WUBC => {
+            (PlayerSpec::User, PlayerSpec::Engine(EngineSpec::default()))
         }
-
-        let mut legal_moves: Vec<String> = game_board
-            .generate_moves()
-            .iter()
-            .map(|x| x.to_string())
-            .collect();
-        legal_moves.sort();
-        println!(
-            "legal_moves = {:?}, len = {}",
-            legal_moves,
-            legal_moves.len()
-        );
-        if config.evaluate_user {
-            let black_best = get_best_move(&game_board, config.depth).unwrap();
-            println!(
-                "Black best move = {}, value = {}",
-                black_best.m, black_best.value
-            );
+        GameMode::BUWC => {
+            (PlayerSpec::Engine(EngineSpec::default()), PlayerSpec::User)
         }
-        handle_user_move(&mut game_board, &mut config);
-        if game_board.checkmate() {
-            println!("Chechmate! Black won!");
-            break;
-        } else if game_board.stalemate() {
-            println!("Stalemate! Game over.");
-            break;
+        GameMode::UU => (PlayerSpec::User, PlayerSpec::User),
+        GameMode::Replay | GameMode::Match | GameMode::Sprt => {
+            anyhow::bail!("{:?} mode is not a single game", config.mode)
         }
-    }
+    };
+    let mut white = make_player(config.white.as_ref().unwrap_or(&white), &config)
+        .context("Failed to create white player")?;
+    let mut black = make_player(config.black.as_ref().unwrap_or(&black), &config)
+        .context("Failed to create black player")?;
+    play_game(&config, game_board, white.as_mut(), black.as_mut());
+    Ok(())
 }
@@ -1,5 +1,19 @@
 pub mod alpha_beta;
+pub mod bench;
 pub mod board_pretty_print;
 pub mod board_value;
+pub mod book;
 pub mod config;
+pub mod draw;
+pub mod engine_match;
+pub mod fen;
 pub mod game_modes;
+pub mod move_ordering;
+pub mod perft;
+pub mod pgn;
+pub mod player;
+pub mod san;
+pub mod search_limits;
+pub mod search_stats;
+pub mod sprt;
+pub mod transposition_table;
@@ -1,15 +1,30 @@
 use chess_alpha_beta::config::{Config, GameMode};
-use chess_alpha_beta::game_modes::{
-    black_user_with_white_computer, computer_with_computer,
-    white_user_with_black_computer,
-};
+use chess_alpha_beta::engine_match::run_match;
+use chess_alpha_beta::game_modes::{initial_board, play, replay};
+use chess_alpha_beta::sprt::run_sprt;
 use clap::Parser;
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let config = Config::parse();
+    anyhow::ensure!(
+        config.mode != GameMode::Replay || config.load_pgn.is_some(),
+        "Replay mode needs --load-pgn"
+    );
     match config.mode {
-        GameMode::CC => computer_with_computer(config),
-        GameMode::WUBC => white_user_with_black_computer(config),
-        GameMode::BUWC => black_user_with_white_computer(config),
+        GameMode::Replay => {
+            let game_board = initial_board(&config)?;
+            replay(config, game_board)
+        }
+        GameMode::Match => {
+            run_match(config)?;
+        }
+        GameMode::Sprt => {
+            run_sprt(config)?;
+        }
+        _ => {
+            let game_board = initial_board(&config)?;
+            play(config, game_board)?
+        }
     };
+    Ok(())
 }
@@ -0,0 +1,134 @@
+use pleco::{BitMove, Board};
+
+/// Maximum search ply tracked by killer moves
+const MAX_PLY: usize = 128;
+
+const HASH_MOVE_SCORE: i32 = 3_000_000;
+const CAPTURE_SCORE: i32 = 2_000_000;
+const KILLER_SCORE: i32 = 1_000_000;
+/// History scores are halved when one of them reaches this limit so they
+/// never outrank killers
+const HISTORY_LIMIT: i32 = KILLER_SCORE / 2;
+
+/// Most valuable victim - least valuable attacker score of a capture.
+/// Non-captures score 0.
+pub fn mvv_lva(board: &Board, m: BitMove) -> i32 {
+    let victim = i32::from(board.captured_piece(m).value());
+    if victim == 0 {
+        return 0;
+    }
+    let attacker = i32::from(board.moved_piece(m).type_of().value());
+    victim * 16 - attacker
+}
+
+/// Per-thread state for ordering moves: killer moves per ply and history
+/// heuristic scores. Better moves are searched first, which makes alpha-beta
+/// cutoffs happen earlier.
+pub struct MoveOrdering {
+    /// Two most recent quiet moves which caused a beta cutoff on each ply
+    killers: Vec<[Option<BitMove>; 2]>,
+    /// Cutoff scores of quiet moves indexed by side, source and destination
+    history: Box<[[[i32; 64]; 64]; 2]>,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        Self {
+            killers: vec![[None; 2]; MAX_PLY],
+            history: Box::new([[[0; 64]; 64]; 2]),
+        }
+    }
+}
+
+impl MoveOrdering {
+    fn history_entry(&mut self, board: &Board, m: BitMove) -> &mut i32 {
+        &mut self.history[board.turn() as usize][m.get_src_u8() as usize]
+            [m.get_dest_u8() as usize]
+    }
+
+    fn score(
+        &self,
+        board: &Board,
+        m: BitMove,
+        hash_move: Option<BitMove>,
+        ply: usize,
+    ) -> i32 {
+        if Some(m) == hash_move {
+            HASH_MOVE_SCORE
+        } else if m.is_capture() || m.is_promo() {
+            CAPTURE_SCORE
+                + mvv_lva(board, m)
+                + i32::from(m.is_promo()) * i32::from(m.promo_piece().value())
+        } else if let Some(i) = self
+            .killers
+            .get(ply)
+            .and_then(|k| k.iter().position(|&x| x == Some(m)))
+        {
+            KILLER_SCORE - i as i32
+        } else {
+            self.history[board.turn() as usize][m.get_src_u8() as usize]
+                [m.get_dest_u8() as usize]
+        }
+    }
+
+    /// Sorts legal moves of `board` from the most to the least promising:
+    /// the hash move, captures and promotions by MVV-LVA, killer moves of
+    /// `ply` and then quiet moves by history score. With `shuffle` moves of
+    /// equal rank are taken in random order, which is the same for the same
+    /// position and `seed` if it is given.
+    pub fn order_moves(
+        &self,
+        board: &Board,
+        moves: &[BitMove],
+        hash_move: Option<BitMove>,
+        ply: u32,
+        shuffle: bool,
+        seed: Option<u64>,
+    ) -> Vec<BitMove> {
+        let mut moves = moves.to_vec();
+        if shuffle {
+            use rand::SeedableRng;
+            use rand::seq::SliceRandom;
+            match seed {
+                Some(seed) => moves.shuffle(
+                    &mut rand::rngs::SmallRng::seed_from_u64(seed ^ board.zobrist()),
+                ),
+                None => moves.shuffle(&mut rand::rng()),
+            }
+        }
+        // Stable sort keeps the random order among equal scores
+        moves
+            .sort_by_cached_key(|&m| -self.score(board, m, hash_move, ply as usize));
+        moves
+    }
+
+    /// Remembers a quiet move `m` which caused a beta cutoff at `ply` with
+    /// remaining `depth`. Captures are already ordered by MVV-LVA.
+    pub fn update_on_cutoff(
+        &mut self,
+        board: &Board,
+        m: BitMove,
+        ply: u32,
+        depth: u32,
+    ) {
+        if m.is_capture() || m.is_promo() {
+            return;
+        }
+        if let Some(killers) = self.killers.get_mut(ply as usize)
+            && killers[0] != Some(m)
+        {
+            killers[1] = killers[0];
+            killers[0] = Some(m);
+        }
+        let bonus = (depth * depth) as i32;
+        let entry = self.history_entry(board, m);
+        *entry += bonus;
+        if *entry >= HISTORY_LIMIT {
+            self.history
+                .iter_mut()
+                .flatten()
+                .flatten()
+                .for_each(|x| *x /= 2);
+        }
+    }
+}
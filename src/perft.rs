@@ -0,0 +1,47 @@
+use rayon::prelude::*;
+
+/// Number of leaf nodes of the legal move tree of `board` at `depth`
+pub fn perft(board: &pleco::Board, depth: u32) -> u64 {
+    if depth <= 1 {
+        return if depth == 0 {
+            1
+        } else {
+            board.generate_moves().len() as u64
+        };
+    }
+    divide(board, depth).iter().map(|(_, nodes)| nodes).sum()
+}
+
+/// Leaf nodes at `depth` after every legal move of `board`. Root moves are
+/// counted in parallel.
+pub fn divide(board: &pleco::Board, depth: u32) -> Vec<(pleco::BitMove, u64)> {
+    let moves: Vec<pleco::BitMove> =
+        board.generate_moves().iter().copied().collect();
+    moves
+        .par_iter()
+        .map(|&m| {
+            let mut experiment_board = board.clone();
+            experiment_board.apply_move(m);
+            let nodes = perft_impl(&mut experiment_board, depth.saturating_sub(1));
+            (m, nodes)
+        })
+        .collect()
+}
+
+fn perft_impl(board: &mut pleco::Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = board.generate_moves();
+    // Leaves are counted without playing them
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let mut nodes = 0;
+    for &m in moves.iter() {
+        board.apply_move(m);
+        nodes += perft_impl(board, depth - 1);
+        board.undo_move();
+    }
+    nodes
+}
@@ -0,0 +1,298 @@
+use crate::alpha_beta::{EvaluatedMove, ValueType};
+use crate::board_value::plies_to_mate;
+use crate::draw::draw_reason;
+use crate::fen::parse_fen;
+use crate::san::{move_to_san, san_to_move};
+use anyhow::Context;
+
+/// PGN lines are wrapped at this width
+const MAX_LINE_LEN: usize = 80;
+
+/// Seven Tag Roster of a PGN game without Result which is derived from the
+/// final position
+#[derive(Debug, Clone)]
+pub struct PgnHeaders {
+    pub event: String,
+    pub site: String,
+    /// Date in "YYYY.MM.DD" format
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnHeaders {
+    fn default() -> Self {
+        Self {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: today(),
+            round: "-".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+/// Engine evaluation written as a comment after a move
+#[derive(Debug, Copy, Clone)]
+pub struct MoveComment {
+    /// Value from White's point of view
+    pub value: ValueType,
+    /// Depth of the search which found the value
+    pub depth: u32,
+}
+
+impl From<&EvaluatedMove> for MoveComment {
+    fn from(x: &EvaluatedMove) -> Self {
+        Self {
+            value: x.value,
+            depth: x.stats.depth,
+        }
+    }
+}
+
+impl std::fmt::Display for MoveComment {
+    /// "value/depth" or "#moves/depth" for mates
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match plies_to_mate(self.value) {
+            Some(plies) => {
+                write!(f, "#{}/{}", (plies + plies.signum()) / 2, self.depth)
+            }
+            None => write!(f, "{}/{}", self.value, self.depth),
+        }
+    }
+}
+
+/// Current date in UTC as PGN expects it
+fn today() -> String {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs() / 86400) as i64;
+    // Civil date from days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// "1-0", "0-1", "1/2-1/2" or "*" for a game in progress
+pub fn game_result(board: &pleco::Board) -> &'static str {
+    if board.checkmate() {
+        match board.turn() {
+            pleco::Player::White => "0-1",
+            pleco::Player::Black => "1-0",
+        }
+    } else if board.stalemate() || draw_reason(board).is_some() {
+        "1/2-1/2"
+    } else {
+        "*"
+    }
+}
+
+/// Moves played on `board` and the position before the first of them.
+/// Like `draw::position_history` only moves played on this board or its
+/// `parallel_clone` are known.
+pub fn played_moves(board: &pleco::Board) -> (pleco::Board, Vec<pleco::BitMove>) {
+    let mut start = board.parallel_clone();
+    let mut moves = Vec::new();
+    while start.depth() > 0 {
+        moves.extend(start.last_move());
+        start.undo_move();
+    }
+    moves.reverse();
+    (start, moves)
+}
+
+/// Writes the game played on `board` in PGN. `comments[i]` is written after
+/// the i-th move if present. A game which has not started from the initial
+/// position gets SetUp and FEN tags.
+pub fn write_pgn(
+    board: &pleco::Board,
+    headers: &PgnHeaders,
+    comments: &[Option<MoveComment>],
+) -> String {
+    let (mut game_board, moves) = played_moves(board);
+    let result = game_result(board);
+    let mut pgn = String::new();
+    for (name, value) in [
+        ("Event", &headers.event),
+        ("Site", &headers.site),
+        ("Date", &headers.date),
+        ("Round", &headers.round),
+        ("White", &headers.white),
+        ("Black", &headers.black),
+        ("Result", &result.to_string()),
+    ] {
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+    }
+    let start_fen = game_board.fen();
+    if start_fen != pleco::Board::start_pos().fen() {
+        pgn.push_str("[SetUp \"1\"]\n");
+        pgn.push_str(&format!("[FEN \"{start_fen}\"]\n"));
+    }
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    for (i, &m) in moves.iter().enumerate() {
+        let move_number = game_board.moves_played() / 2 + 1;
+        match game_board.turn() {
+            pleco::Player::White => tokens.push(format!("{move_number}.")),
+            pleco::Player::Black if i == 0 => {
+                tokens.push(format!("{move_number}..."))
+            }
+            pleco::Player::Black => {}
+        }
+        tokens.push(move_to_san(&game_board, m));
+        game_board.apply_move(m);
+        if let Some(Some(comment)) = comments.get(i) {
+            tokens.push(format!("{{{comment}}}"));
+        }
+    }
+    tokens.push(result.to_string());
+
+    let mut line_len = 0;
+    for token in tokens {
+        if line_len > 0 && line_len + 1 + token.len() > MAX_LINE_LEN {
+            pgn.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            pgn.push(' ');
+            line_len += 1;
+        }
+        line_len += token.len();
+        pgn.push_str(&token);
+    }
+    pgn.push('\n');
+    pgn
+}
+
+/// Game read from a PGN file. Comments, variations and annotations are dropped.
+#[derive(Debug, Clone, Default)]
+pub struct PgnGame {
+    /// Tag pairs in the order of the file
+    pub tags: Vec<(String, String)>,
+    /// Moves of the main line in SAN
+    pub moves: Vec<String>,
+    /// "1-0", "0-1", "1/2-1/2" or "*"
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Position the game started from: the FEN tag or the initial position
+    pub fn start_board(&self) -> anyhow::Result<pleco::Board> {
+        match self.tag("FEN") {
+            Some(fen) => parse_fen(fen),
+            None => Ok(pleco::Board::start_pos()),
+        }
+    }
+
+    /// Plays all moves of the game. Moves stay on the board, so they can be
+    /// undone and exported again.
+    pub fn replay(&self) -> anyhow::Result<pleco::Board> {
+        let mut board = self.start_board()?;
+        for (i, san) in self.moves.iter().enumerate() {
+            let m = san_to_move(&board, san)
+                .with_context(|| format!("Move {} of the game", i / 2 + 1))?;
+            board.apply_move(m);
+        }
+        Ok(board)
+    }
+}
+
+/// Splits PGN text into games
+pub fn parse_pgn(text: &str) -> anyhow::Result<Vec<PgnGame>> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut in_movetext = false;
+    let mut variation_depth = 0;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                chars.by_ref().find(|&x| x == '}');
+            }
+            ';' => {
+                chars.by_ref().find(|&x| x == '\n');
+            }
+            '(' => variation_depth += 1,
+            ')' => {
+                anyhow::ensure!(variation_depth > 0, "Unexpected ')'");
+                variation_depth -= 1;
+            }
+            _ if variation_depth > 0 || c.is_whitespace() => {}
+            '[' if !in_movetext => {
+                let tag: String = chars.by_ref().take_while(|&x| x != ']').collect();
+                let (name, value) = tag
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .with_context(|| format!("Invalid tag '[{tag}]'"))?;
+                let value = value
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|x| x.strip_suffix('"'))
+                    .with_context(|| format!("Invalid tag '[{tag}]'"))?;
+                game.tags.push((
+                    name.to_string(),
+                    value.replace("\\\"", "\"").replace("\\\\", "\\"),
+                ));
+            }
+            _ => {
+                in_movetext = true;
+                let mut token = String::from(c);
+                while let Some(&x) = chars.peek() {
+                    if x.is_whitespace() || "{;()".contains(x) {
+                        break;
+                    }
+                    token.push(x);
+                    chars.next();
+                }
+                match token.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => {
+                        game.result = token;
+                        games.push(std::mem::take(&mut game));
+                        in_movetext = false;
+                    }
+                    // Numeric annotation glyph
+                    _ if token.starts_with('$') => {}
+                    _ => {
+                        // Move number with or without the move: "12.",
+                        // "12...", "12.e4"
+                        let san = match token.split_once('.') {
+                            Some((number, san))
+                                if number.chars().all(|x| x.is_ascii_digit()) =>
+                            {
+                                san.trim_start_matches('.')
+                            }
+                            _ => &token,
+                        };
+                        if !san.is_empty() {
+                            game.moves.push(san.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if in_movetext {
+        // Game without a result at the end of the file
+        game.result = "*".to_string();
+        games.push(game);
+    }
+    Ok(games)
+}
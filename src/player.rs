@@ -0,0 +1,542 @@
+use crate::alpha_beta::{EvaluatedMove, iterative_deepening};
+use crate::book::OpeningBook;
+use crate::config::{Config, EngineSpec};
+use crate::pgn::{MoveComment, played_moves};
+use crate::san::{line_to_san, move_to_san, san_to_move};
+use crate::search_limits::Clock;
+use crate::transposition_table::TranspositionTable;
+use anyhow::Context;
+use std::io::{BufRead, Write};
+
+/// Name of the computer player in PGN headers
+pub const COMPUTER_NAME: &str =
+    concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"));
+pub const USER_NAME: &str = "User";
+
+/// Search limit changed by a user during the game
+#[derive(Debug, Copy, Clone)]
+pub enum Setting {
+    Depth(std::num::NonZeroU32),
+    /// None removes the limit
+    MaxNodes(Option<u64>),
+    /// Milliseconds, None removes the limit
+    MoveTime(Option<u64>),
+}
+
+/// What a player does on its turn
+#[derive(Debug, Clone)]
+pub enum Turn {
+    /// Play a legal move. The comment is saved to PGN.
+    Move(pleco::BitMove, Option<MoveComment>),
+    /// Take back the last two half moves
+    Undo,
+    /// Save the game to the given PGN file or to the one from the config
+    SavePgn(Option<String>),
+    /// Change search limits of every player
+    Configure(Setting),
+}
+
+/// Side of a game which chooses moves
+pub trait Player: Send {
+    /// Name for PGN headers
+    fn name(&self) -> String;
+
+    /// Called when the player is to move on `board`. Every turn other than
+    /// `Turn::Move` is followed by another call.
+    fn play(&mut self, board: &pleco::Board) -> anyhow::Result<Turn>;
+
+    /// Applies a setting changed by any player
+    fn configure(&mut self, _setting: Setting) {}
+
+    /// Forgets the previous game before another one is played
+    fn new_game(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Human players want to see the board before every move
+    fn is_human(&self) -> bool {
+        false
+    }
+}
+
+fn side_name(board: &pleco::Board) -> &'static str {
+    match board.turn() {
+        pleco::Player::White => "White",
+        pleco::Player::Black => "Black",
+    }
+}
+
+/// Reads a line from stdin. The end of input is an error.
+pub(crate) fn input(promt: &str) -> anyhow::Result<String> {
+    let mut ret = String::new();
+    print!("{}", promt);
+    std::io::stdout().flush().expect("flush failed");
+    let len = std::io::stdin()
+        .read_line(&mut ret)
+        .context("Failed to read line")?;
+    anyhow::ensure!(len > 0, "End of input");
+    Ok(ret.trim().to_string())
+}
+
+enum UserCommand {
+    MakeMove(String),
+    Undo,
+    SavePgn(Option<String>),
+    ChangeDepth(std::num::NonZeroU32),
+    ChangeMaxNodes(Option<u64>),
+    ChangeMoveTime(Option<u64>),
+    ChangeEvaluateUser(bool),
+}
+
+/// None if `s` is empty or an invalid command
+fn parse_user_command(s: &str) -> Option<UserCommand> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    let c = *bytes.first()?;
+    if c == b'd' {
+        i += 1;
+        // "dn" and "dt" change node and time limits, 0 removes the limit
+        let limit = bytes.get(i).copied().filter(|x| *x == b'n' || *x == b't');
+        if limit.is_some() {
+            i += 1;
+        }
+        // The space tells "d 5" from the pawn move "d5"
+        let value_start = i;
+        while i < s.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if i > value_start && i < s.len() && bytes[i].is_ascii_digit() {
+            let mut value = String::from(char::from(bytes[i]));
+            i += 1;
+            while i < s.len() && bytes[i].is_ascii_digit() {
+                value.push(char::from(bytes[i]));
+                i += 1;
+            }
+            if i == s.len() {
+                return match limit {
+                    Some(b'n') => Some(UserCommand::ChangeMaxNodes(
+                        Some(value.parse::<u64>().ok()?).filter(|x| *x != 0),
+                    )),
+                    Some(_) => Some(UserCommand::ChangeMoveTime(
+                        Some(value.parse::<u64>().ok()?).filter(|x| *x != 0),
+                    )),
+                    None => Some(UserCommand::ChangeDepth(
+                        value.parse::<std::num::NonZeroU32>().ok()?,
+                    )),
+                };
+            }
+        }
+    } else if c == b'e' {
+        i += 1;
+        let value_start = i;
+        while i < s.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+            continue;
+        }
+        if i > value_start && i + 1 == s.len() {
+            if bytes[i] == b'0' {
+                return Some(UserCommand::ChangeEvaluateUser(false));
+            } else if bytes[i] == b'1' {
+                return Some(UserCommand::ChangeEvaluateUser(true));
+            }
+        }
+    } else if c == b'u' {
+        return Some(UserCommand::Undo);
+    } else if c == b's' {
+        let path = s[1..].trim();
+        return Some(UserCommand::SavePgn(
+            (!path.is_empty()).then(|| path.to_string()),
+        ));
+    }
+    Some(UserCommand::MakeMove(s.to_string()))
+}
+
+/// Principal variation starting from `board` in SAN
+pub(crate) fn pv_to_string(board: &pleco::Board, pv: &[pleco::BitMove]) -> String {
+    line_to_san(board, pv).join(" ")
+}
+
+/// Legal moves of `board` in SAN in alphabetical order
+fn legal_moves_san(board: &pleco::Board) -> Vec<String> {
+    let mut legal_moves: Vec<String> = board
+        .generate_moves()
+        .iter()
+        .map(|&x| move_to_san(board, x))
+        .collect();
+    legal_moves.sort();
+    legal_moves
+}
+
+/// Finds a legal move of `board` written in SAN or coordinate notation
+fn parse_move(board: &pleco::Board, s: &str) -> anyhow::Result<pleco::BitMove> {
+    san_to_move(board, s).or_else(|e| {
+        board
+            .generate_moves()
+            .iter()
+            .copied()
+            .find(|m| m.to_string() == s)
+            .ok_or(e)
+    })
+}
+
+/// Searches for the computer move. Time spent is charged to `clock`.
+pub(crate) fn search_best_move(
+    game_board: &pleco::Board,
+    config: &Config,
+    tt: &TranspositionTable,
+    clock: Option<&mut Clock>,
+) -> EvaluatedMove {
+    let options = config.search_options();
+    let limits = config.search_limits(clock.as_deref().copied());
+    let start = std::time::Instant::now();
+    let best = iterative_deepening(game_board, &limits, tt, &options, |depth, x| {
+        if config.quiet {
+            return;
+        }
+        println!(
+            "  depth {}: move = {}, value = {}, pv = {}",
+            depth,
+            move_to_san(game_board, x.m),
+            x.value,
+            pv_to_string(game_board, &x.pv)
+        );
+    })
+    .unwrap();
+    if !config.quiet {
+        println!("  {}", best.stats);
+    }
+    if let Some(clock) = clock {
+        clock.spend(start.elapsed());
+        if !config.quiet {
+            println!("  clock = {:.1}s", clock.remaining.as_secs_f64());
+        }
+    }
+    best
+}
+
+fn apply_setting(config: &mut Config, setting: Setting) {
+    match setting {
+        Setting::Depth(d) => config.depth = d,
+        Setting::MaxNodes(n) => config.nodes = n,
+        Setting::MoveTime(t) => config.move_time = t,
+    }
+}
+
+/// Player typing moves and commands to stdin
+pub struct HumanPlayer {
+    /// Limits of the search for a hint when `evaluate_user` is set
+    config: Config,
+    tt: TranspositionTable,
+}
+
+impl HumanPlayer {
+    pub fn new(config: Config) -> Self {
+        Self {
+            tt: TranspositionTable::new(config.hash_size),
+            config,
+        }
+    }
+}
+
+impl Player for HumanPlayer {
+    fn name(&self) -> String {
+        USER_NAME.to_string()
+    }
+
+    fn play(&mut self, board: &pleco::Board) -> anyhow::Result<Turn> {
+        let legal_moves = legal_moves_san(board);
+        println!(
+            "legal_moves = {:?}, len = {}",
+            legal_moves,
+            legal_moves.len()
+        );
+        if self.config.evaluate_user {
+            let best = search_best_move(board, &self.config, &self.tt, None);
+            println!(
+                "{} best move = {}, value = {}, pv = {}",
+                side_name(board),
+                move_to_san(board, best.m),
+                best.value,
+                pv_to_string(board, &best.pv)
+            );
+        }
+        loop {
+            let prompt = format!("Type {} move: ", side_name(board).to_lowercase());
+            let Some(command) = parse_user_command(&input(&prompt)?) else {
+                println!("Invalid command. Try again.");
+                continue;
+            };
+            return Ok(match command {
+                UserCommand::MakeMove(s) => match parse_move(board, &s) {
+                    Ok(m) => Turn::Move(m, None),
+                    Err(e) => {
+                        println!("{}. try again.", e);
+                        continue;
+                    }
+                },
+                UserCommand::Undo => Turn::Undo,
+                UserCommand::SavePgn(path) => Turn::SavePgn(path),
+                UserCommand::ChangeDepth(d) => {
+                    println!("depth = {}", d);
+                    Turn::Configure(Setting::Depth(d))
+                }
+                UserCommand::ChangeMaxNodes(n) => {
+                    println!("nodes = {:?}", n);
+                    Turn::Configure(Setting::MaxNodes(n))
+                }
+                UserCommand::ChangeMoveTime(t) => {
+                    println!("move_time = {:?}", t);
+                    Turn::Configure(Setting::MoveTime(t))
+                }
+                UserCommand::ChangeEvaluateUser(e) => {
+                    println!("evaluate_user = {}", e);
+                    self.config.evaluate_user = e;
+                    continue;
+                }
+            });
+        }
+    }
+
+    fn configure(&mut self, setting: Setting) {
+        apply_setting(&mut self.config, setting);
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+}
+
+/// This program searching with its own limits, clock and transposition table
+pub struct EnginePlayer {
+    name: String,
+    config: Config,
+    tt: TranspositionTable,
+    clock: Option<Clock>,
+    book: Option<OpeningBook>,
+}
+
+impl EnginePlayer {
+    pub fn new(config: Config) -> Self {
+        Self {
+            name: COMPUTER_NAME.to_string(),
+            tt: TranspositionTable::new(config.hash_size),
+            clock: config.initial_clock(),
+            config,
+            book: None,
+        }
+    }
+
+    /// Plays moves from `book` while there are any before searching
+    pub fn with_book(self, book: Option<OpeningBook>) -> Self {
+        Self { book, ..self }
+    }
+
+    /// Engine with the settings of `spec` instead of the ones from the config
+    /// and its opening book. The settings are added to the name.
+    pub fn with_spec(config: &Config, spec: &EngineSpec) -> anyhow::Result<Self> {
+        let config = spec.apply(config);
+        let book = config.opening_book()?;
+        let name = match spec.to_string() {
+            options if options.is_empty() => COMPUTER_NAME.to_string(),
+            options => format!("{COMPUTER_NAME} {options}"),
+        };
+        Ok(Self {
+            name,
+            ..Self::new(config)
+        }
+        .with_book(book))
+    }
+}
+
+impl Player for EnginePlayer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn play(&mut self, board: &pleco::Board) -> anyhow::Result<Turn> {
+        if let Some(m) = self.book.as_ref().and_then(|x| {
+            x.choose(board, self.config.book_selection, self.config.seed)
+        }) {
+            if let Some(clock) = &mut self.clock {
+                clock.spend(std::time::Duration::ZERO);
+            }
+            if !self.config.quiet {
+                println!(
+                    "{} move = {} (book)",
+                    side_name(board),
+                    move_to_san(board, m)
+                );
+            }
+            return Ok(Turn::Move(m, None));
+        }
+        let best =
+            search_best_move(board, &self.config, &self.tt, self.clock.as_mut());
+        if !self.config.quiet {
+            println!(
+                "{} move = {}, value = {}, pv = {}",
+                side_name(board),
+                move_to_san(board, best.m),
+                best.value,
+                pv_to_string(board, &best.pv)
+            );
+        }
+        Ok(Turn::Move(best.m, Some(MoveComment::from(&best))))
+    }
+
+    fn configure(&mut self, setting: Setting) {
+        apply_setting(&mut self.config, setting);
+    }
+
+    fn new_game(&mut self) -> anyhow::Result<()> {
+        self.tt.clear();
+        self.clock = self.config.initial_clock();
+        Ok(())
+    }
+}
+
+/// Player making moves from a prepared list one by one
+pub struct ScriptedPlayer {
+    name: String,
+    /// Moves in SAN or coordinate notation
+    moves: std::collections::VecDeque<String>,
+}
+
+impl ScriptedPlayer {
+    pub fn new(name: &str, moves: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            name: name.to_string(),
+            moves: moves.into_iter().collect(),
+        }
+    }
+
+    /// Reads whitespace separated moves from a file
+    pub fn from_file(path: &std::path::Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        Ok(Self::new(
+            &path.display().to_string(),
+            text.split_whitespace().map(str::to_string),
+        ))
+    }
+}
+
+impl Player for ScriptedPlayer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn play(&mut self, board: &pleco::Board) -> anyhow::Result<Turn> {
+        let s = self
+            .moves
+            .pop_front()
+            .context("No moves left in the script")?;
+        let m = parse_move(board, &s)?;
+        println!("{} move = {}", side_name(board), move_to_san(board, m));
+        Ok(Turn::Move(m, None))
+    }
+}
+
+/// UCI engine reachable over TCP, for example another instance of
+/// chess-alpha-beta-uci behind `socat TCP-LISTEN:9000,fork EXEC:chess-alpha-beta-uci`
+pub struct RemotePlayer {
+    name: String,
+    reader: std::io::BufReader<std::net::TcpStream>,
+    writer: std::net::TcpStream,
+    config: Config,
+}
+
+impl RemotePlayer {
+    pub fn connect(address: &str, config: Config) -> anyhow::Result<Self> {
+        let writer = std::net::TcpStream::connect(address)
+            .with_context(|| format!("Failed to connect to '{address}'"))?;
+        let mut player = Self {
+            name: address.to_string(),
+            reader: std::io::BufReader::new(writer.try_clone()?),
+            writer,
+            config,
+        };
+        player.send("uci")?;
+        let id = player.read_until("uciok")?;
+        if let Some(name) = id.iter().find_map(|x| x.strip_prefix("id name ")) {
+            player.name = name.to_string();
+        }
+        player.send("ucinewgame")?;
+        player.send("isready")?;
+        player.read_until("readyok")?;
+        Ok(player)
+    }
+
+    fn send(&mut self, command: &str) -> anyhow::Result<()> {
+        writeln!(self.writer, "{command}").context("Failed to send to the engine")
+    }
+
+    /// Reads lines until one starts with `prefix` and returns all of them
+    fn read_until(&mut self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            let len = self
+                .reader
+                .read_line(&mut line)
+                .context("Failed to read from the engine")?;
+            anyhow::ensure!(len > 0, "Engine closed the connection");
+            let line = line.trim().to_string();
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return Ok(lines);
+            }
+        }
+    }
+}
+
+impl Player for RemotePlayer {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn play(&mut self, board: &pleco::Board) -> anyhow::Result<Turn> {
+        let (start, moves) = played_moves(board);
+        let moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+        let mut position = format!("position fen {}", start.fen());
+        if !moves.is_empty() {
+            position += &format!(" moves {}", moves.join(" "));
+        }
+        self.send(&position)?;
+        let mut go = format!("go depth {}", self.config.depth);
+        if let Some(nodes) = self.config.nodes {
+            go += &format!(" nodes {nodes}");
+        }
+        if let Some(move_time) = self.config.move_time {
+            go += &format!(" movetime {move_time}");
+        }
+        self.send(&go)?;
+        let lines = self.read_until("bestmove")?;
+        let best = lines
+            .last()
+            .and_then(|x| x.split_whitespace().nth(1))
+            .context("Missing move in 'bestmove'")?;
+        let m = parse_move(board, best)?;
+        if !self.config.quiet {
+            println!(
+                "{} move = {} by {}",
+                side_name(board),
+                move_to_san(board, m),
+                self.name
+            );
+        }
+        Ok(Turn::Move(m, None))
+    }
+
+    fn configure(&mut self, setting: Setting) {
+        apply_setting(&mut self.config, setting);
+    }
+
+    fn new_game(&mut self) -> anyhow::Result<()> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        self.read_until("readyok")?;
+        Ok(())
+    }
+}
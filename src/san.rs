@@ -0,0 +1,160 @@
+use anyhow::Context;
+use pleco::{BitMove, PieceType};
+
+/// Converts legal move `m` of `board` into Standard Algebraic Notation, for
+/// example "Nbd7", "exd5", "O-O" or "e8=Q+"
+pub fn move_to_san(board: &pleco::Board, m: BitMove) -> String {
+    let mut san = if m.is_king_castle() {
+        "O-O".to_string()
+    } else if m.is_queen_castle() {
+        "O-O-O".to_string()
+    } else {
+        let piece = board.moved_piece(m).type_of();
+        let src = m.get_src().to_string();
+        let mut san = String::new();
+        if piece == PieceType::P {
+            if m.is_capture() {
+                san.push_str(&src[..1]);
+            }
+        } else {
+            san.push(piece.char_upper());
+            san.push_str(&disambiguation(board, m, piece, &src));
+        }
+        if m.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&m.get_dest().to_string());
+        if m.is_promo() {
+            san.push('=');
+            san.push(m.promo_piece().char_upper());
+        }
+        san
+    };
+    let mut experiment_board = board.clone();
+    experiment_board.apply_move(m);
+    if experiment_board.checkmate() {
+        san.push('#');
+    } else if experiment_board.in_check() {
+        san.push('+');
+    }
+    san
+}
+
+/// Converts moves played one after another from `board` into SAN
+pub fn line_to_san(board: &pleco::Board, moves: &[BitMove]) -> Vec<String> {
+    let mut experiment_board = board.clone();
+    moves
+        .iter()
+        .map(|&m| {
+            let san = move_to_san(&experiment_board, m);
+            experiment_board.apply_move(m);
+            san
+        })
+        .collect()
+}
+
+/// File, rank or both of the source square when another piece of the same type
+/// can move to the same square
+fn disambiguation(
+    board: &pleco::Board,
+    m: BitMove,
+    piece: PieceType,
+    src: &str,
+) -> String {
+    let others: Vec<String> = board
+        .generate_moves()
+        .iter()
+        .filter(|x| {
+            x.get_dest() == m.get_dest()
+                && x.get_src() != m.get_src()
+                && board.moved_piece(**x).type_of() == piece
+        })
+        .map(|x| x.get_src().to_string())
+        .collect();
+    if others.is_empty() {
+        String::new()
+    } else if others.iter().all(|x| x[..1] != src[..1]) {
+        src[..1].to_string()
+    } else if others.iter().all(|x| x[1..] != src[1..]) {
+        src[1..].to_string()
+    } else {
+        src.to_string()
+    }
+}
+
+/// Finds the legal move of `board` written in Standard Algebraic Notation.
+/// Check and annotation suffixes are ignored, castling may be written with
+/// zeros and promotion without "=".
+pub fn san_to_move(board: &pleco::Board, san: &str) -> anyhow::Result<BitMove> {
+    let stripped = san.trim_end_matches(['+', '#', '!', '?']);
+    let legal_moves = board.generate_moves();
+    let castle = match stripped {
+        "O-O" | "0-0" => Some(true),
+        "O-O-O" | "0-0-0" => Some(false),
+        _ => None,
+    };
+    if let Some(king_side) = castle {
+        return legal_moves
+            .iter()
+            .copied()
+            .find(|m| {
+                if king_side {
+                    m.is_king_castle()
+                } else {
+                    m.is_queen_castle()
+                }
+            })
+            .with_context(|| format!("Illegal move '{san}'"));
+    }
+
+    let mut chars: Vec<char> = stripped.chars().filter(|&c| c != 'x').collect();
+    let piece = match chars.first() {
+        Some(&c) if "NBRQK".contains(c) => {
+            chars.remove(0);
+            char_to_piece(c)
+        }
+        _ => PieceType::P,
+    };
+    let mut promo = None;
+    if let Some(&c) = chars.last()
+        && "NBRQ".contains(c)
+    {
+        chars.pop();
+        chars.pop_if(|x| *x == '=');
+        promo = Some(char_to_piece(c));
+    }
+    anyhow::ensure!(chars.len() >= 2, "Invalid move '{san}'");
+    let dest: String = chars.split_off(chars.len() - 2).into_iter().collect();
+    let from_file = chars.iter().copied().find(|c| ('a'..='h').contains(c));
+    let from_rank = chars.iter().copied().find(|c| ('1'..='8').contains(c));
+    anyhow::ensure!(
+        chars.len()
+            == usize::from(from_file.is_some()) + usize::from(from_rank.is_some()),
+        "Invalid move '{san}'"
+    );
+
+    let mut candidates = legal_moves.iter().copied().filter(|&m| {
+        let src = m.get_src().to_string();
+        m.get_dest().to_string() == dest
+            && !m.is_castle()
+            && board.moved_piece(m).type_of() == piece
+            && (m.is_promo().then(|| m.promo_piece()) == promo)
+            && from_file.is_none_or(|x| src.starts_with(x))
+            && from_rank.is_none_or(|x| src.ends_with(x))
+    });
+    let m = candidates
+        .next()
+        .with_context(|| format!("Illegal move '{san}'"))?;
+    anyhow::ensure!(candidates.next().is_none(), "Ambiguous move '{san}'");
+    Ok(m)
+}
+
+fn char_to_piece(c: char) -> PieceType {
+    match c {
+        'N' => PieceType::N,
+        'B' => PieceType::B,
+        'R' => PieceType::R,
+        'Q' => PieceType::Q,
+        _ => PieceType::K,
+    }
+}
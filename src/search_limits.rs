@@ -0,0 +1,87 @@
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Time left on a player's clock
+#[derive(Debug, Copy, Clone)]
+pub struct Clock {
+    pub remaining: Duration,
+    /// Time added after every move
+    pub increment: Duration,
+    /// Moves until the next time control, if any
+    pub moves_to_go: Option<u32>,
+}
+
+impl Clock {
+    /// Part of the remaining time which is always kept in reserve
+    const SAFETY_MARGIN: Duration = Duration::from_millis(50);
+
+    /// Moves the remaining time is split between without a time control
+    const EXPECTED_MOVES: u32 = 30;
+
+    /// Time which can be spent on the next move
+    pub fn budget(&self) -> Duration {
+        let moves = self
+            .moves_to_go
+            .map_or(Self::EXPECTED_MOVES, |x| x.clamp(2, Self::EXPECTED_MOVES));
+        let budget = self.remaining / moves + self.increment * 3 / 4;
+        budget.min(self.remaining.saturating_sub(Self::SAFETY_MARGIN))
+    }
+
+    /// Charges a move which took `elapsed` to the clock
+    pub fn spend(&mut self, elapsed: Duration) {
+        self.remaining = self.remaining.saturating_sub(elapsed) + self.increment;
+    }
+}
+
+/// Shared flag to stop a running search from another thread
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// When a search must stop: at the first limit reached. Only the node and time
+/// limits wait until depth 1 is completed so that there is a move to return.
+#[derive(Debug, Clone)]
+pub struct SearchLimits {
+    pub max_depth: NonZeroU32,
+    /// Maximum number of visited nodes
+    pub max_nodes: Option<u64>,
+    /// Fixed time for a move
+    pub move_time: Option<Duration>,
+    /// Clock of the side to move, used to derive time for a move
+    pub clock: Option<Clock>,
+    /// Stops the search as soon as possible, even before depth 1 is completed
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl SearchLimits {
+    /// Limits the search only by depth
+    pub fn depth(max_depth: NonZeroU32) -> Self {
+        Self {
+            max_depth,
+            max_nodes: None,
+            move_time: None,
+            clock: None,
+            cancellation: None,
+        }
+    }
+
+    /// Time which can be spent on the search if it is limited by time
+    pub fn time_budget(&self) -> Option<Duration> {
+        let clock_budget = self.clock.map(|x| x.budget());
+        match (self.move_time, clock_budget) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
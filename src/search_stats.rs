@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Amount of work done by a search. Counters are accumulated over all
+/// iterations of iterative deepening and all threads.
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+    /// Visited nodes of the main and quiescence search
+    pub nodes: u64,
+    /// Positions valued by the static evaluation
+    pub leaf_evaluations: u64,
+    /// Beta cutoffs in the main search
+    pub beta_cutoffs: u64,
+    /// Beta cutoffs caused by the first searched move
+    pub first_move_cutoffs: u64,
+    /// Transposition table probes which found the position
+    pub tt_hits: u64,
+    pub elapsed: Duration,
+    /// Last completed depth
+    pub depth: u32,
+    /// Maximum ply reached including quiescence search
+    pub selective_depth: u32,
+}
+
+impl SearchStats {
+    pub fn nodes_per_second(&self) -> u64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            (self.nodes as f64 / secs) as u64
+        } else {
+            0
+        }
+    }
+
+    /// Part of beta cutoffs caused by the first move, a measure of move
+    /// ordering quality
+    pub fn first_move_cutoff_ratio(&self) -> f64 {
+        if self.beta_cutoffs > 0 {
+            self.first_move_cutoffs as f64 / self.beta_cutoffs as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl std::fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "depth = {}/{}, nodes = {}, nps = {}, time = {:.3}s, leaf evaluations = {}, \
+             beta cutoffs = {} ({:.1}% by first move), tt hits = {}",
+            self.depth,
+            self.selective_depth,
+            self.nodes,
+            self.nodes_per_second(),
+            self.elapsed.as_secs_f64(),
+            self.leaf_evaluations,
+            self.beta_cutoffs,
+            self.first_move_cutoff_ratio() * 100.0,
+            self.tt_hits,
+        )
+    }
+}
+
+/// Counters shared by the search threads
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    pub nodes: AtomicU64,
+    pub leaf_evaluations: AtomicU64,
+    pub beta_cutoffs: AtomicU64,
+    pub first_move_cutoffs: AtomicU64,
+    pub tt_hits: AtomicU64,
+    pub selective_depth: AtomicU32,
+}
+
+impl StatsCounters {
+    pub fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, depth: u32, elapsed: Duration) -> SearchStats {
+        SearchStats {
+            nodes: self.nodes.load(Ordering::Relaxed),
+            leaf_evaluations: self.leaf_evaluations.load(Ordering::Relaxed),
+            beta_cutoffs: self.beta_cutoffs.load(Ordering::Relaxed),
+            first_move_cutoffs: self.first_move_cutoffs.load(Ordering::Relaxed),
+            tt_hits: self.tt_hits.load(Ordering::Relaxed),
+            elapsed,
+            depth,
+            selective_depth: self.selective_depth.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::config::Config;
+use crate::engine_match::{
+    MatchScore, append_pgn, match_players, opening_boards, play_match_game,
+};
+use crate::pgn::game_result;
+use anyhow::Context;
+
+/// Hypothesis accepted by the test
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SprtDecision {
+    /// The candidate is not stronger than by --elo0
+    H0,
+    /// The candidate is stronger by at least --elo1
+    H1,
+}
+
+/// Sequential probability ratio test of a match score
+#[derive(Debug, Copy, Clone)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// H0 is accepted below this log-likelihood ratio
+    pub lower_bound: f64,
+    /// H1 is accepted above this log-likelihood ratio
+    pub upper_bound: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            lower_bound: (beta / (1.0 - alpha)).ln(),
+            upper_bound: ((1.0 - beta) / alpha).ln(),
+        }
+    }
+
+    pub fn decision(&self, score: &MatchScore) -> Option<SprtDecision> {
+        let llr = score.llr(self.elo0, self.elo1);
+        if llr >= self.upper_bound {
+            Some(SprtDecision::H1)
+        } else if llr <= self.lower_bound {
+            Some(SprtDecision::H0)
+        } else {
+            None
+        }
+    }
+}
+
+/// Games shared by the workers
+struct SprtState {
+    /// Number of the next game to start
+    next_game: usize,
+    score: MatchScore,
+    decision: Option<SprtDecision>,
+}
+
+/// Plays games between candidate `--white` and baseline `--black` players on
+/// `--concurrency` threads until the test accepts a hypothesis or `--games`
+/// games are over. Games finished after the decision are not counted. All
+/// games are written to the `--pgn` file.
+pub fn run_sprt(config: Config) -> anyhow::Result<Option<SprtDecision>> {
+    anyhow::ensure!(config.elo0 < config.elo1, "--elo0 must be less than --elo1");
+    for (name, value) in [("alpha", config.alpha), ("beta", config.beta)] {
+        anyhow::ensure!(value > 0.0 && value < 1.0, "--{name} must be in (0, 1)");
+    }
+    let sprt = Sprt::new(config.elo0, config.elo1, config.alpha, config.beta);
+    let openings = opening_boards(&config)?;
+    // Boards and searches of parallel games would be mixed up
+    let game_config = Config {
+        pgn: None,
+        quiet: true,
+        ..config.clone()
+    };
+    let concurrency = config
+        .concurrency
+        .or_else(|| std::thread::available_parallelism().ok())
+        .map_or(1, std::num::NonZeroUsize::get);
+    let max_games = config.games.map_or(usize::MAX, std::num::NonZeroUsize::get);
+    if let Some(path) = &config.pgn {
+        std::fs::write(path, "")
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    }
+    println!(
+        "SPRT: elo0 = {}, elo1 = {}, alpha = {}, beta = {}, LLR bounds = [{:.2}, {:.2}]",
+        sprt.elo0,
+        sprt.elo1,
+        config.alpha,
+        config.beta,
+        sprt.lower_bound,
+        sprt.upper_bound
+    );
+
+    let state = std::sync::Mutex::new(SprtState {
+        next_game: 0,
+        score: MatchScore::default(),
+        decision: None,
+    });
+    let play_games = || -> anyhow::Result<()> {
+        let (mut candidate, mut baseline) = match_players(&game_config)?;
+        loop {
+            let game = {
+                let mut state = state.lock().unwrap();
+                if state.decision.is_some() || state.next_game >= max_games {
+                    return Ok(());
+                }
+                state.next_game += 1;
+                state.next_game - 1
+            };
+            let (board, record, candidate_is_white) = play_match_game(
+                &game_config,
+                &openings,
+                game,
+                candidate.as_mut(),
+                baseline.as_mut(),
+            )?;
+
+            let mut state = state.lock().unwrap();
+            if let Some(path) = &config.pgn {
+                append_pgn(path, &record.pgn(&board))?;
+            }
+            if state.decision.is_some() {
+                continue;
+            }
+            let result = game_result(&board);
+            state.score.add(result, candidate_is_white);
+            state.decision = sprt.decision(&state.score);
+            println!(
+                "Game {}: {} vs {}: {}",
+                game + 1,
+                record.headers.white,
+                record.headers.black,
+                result
+            );
+            println!(
+                "Score of {} vs {}: {}, LLR: {:.2}",
+                candidate.name(),
+                baseline.name(),
+                state.score,
+                state.score.llr(sprt.elo0, sprt.elo1)
+            );
+        }
+    };
+    let worker = || {
+        let result = play_games();
+        if result.is_err() {
+            // Other workers finish their games and stop
+            state.lock().unwrap().next_game = usize::MAX;
+        }
+        result
+    };
+    std::thread::scope(|s| {
+        let workers: Vec<_> = (0..concurrency).map(|_| s.spawn(worker)).collect();
+        workers
+            .into_iter()
+            .try_for_each(|x| x.join().expect("SPRT worker panicked"))
+    })?;
+
+    let state = state.into_inner().unwrap();
+    match state.decision {
+        Some(SprtDecision::H0) => println!("H0 accepted: elo < {}", sprt.elo0),
+        Some(SprtDecision::H1) => println!("H1 accepted: elo > {}", sprt.elo1),
+        None => println!("No decision after {} games", state.score.games()),
+    }
+    Ok(state.decision)
+}
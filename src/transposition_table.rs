@@ -0,0 +1,168 @@
+use crate::alpha_beta::ValueType;
+use crate::board_value::MATE_BOUND;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Keys of the black king squares, made by SplitMix64
+const BLACK_KING_KEYS: [u64; 64] = {
+    let mut keys = [0; 64];
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut i = 0;
+    while i < keys.len() {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+};
+
+/// Zobrist hash of the position. pleco leaves the black king out of its hash,
+/// so positions which differ only by the square of the black king would be
+/// taken for each other. The square is added here.
+pub fn position_key(board: &pleco::Board) -> u64 {
+    let sq = board.king_sq(pleco::Player::Black);
+    board.zobrist() ^ BLACK_KING_KEYS[usize::from(sq.0)]
+}
+
+/// Meaning of a score stored in the table relative to the search window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    /// Score is exact (alpha < score < beta)
+    Exact,
+    /// Search failed high, real score is at least this value
+    Lower,
+    /// Search failed low, real score is at most this value
+    Upper,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Entry {
+    pub depth: u32,
+    pub bound: Bound,
+    pub value: ValueType,
+    pub best_move: Option<pleco::BitMove>,
+}
+
+impl Entry {
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let m = self.best_move.map_or(0, |m| m.get_raw());
+        u64::from(m)
+            | u64::from(self.depth.min(u8::MAX.into())) << 16
+            | bound << 24
+            | u64::from(self.value as u32) << 32
+    }
+
+    fn unpack(data: u64) -> Self {
+        let m = pleco::BitMove::new(data as u16);
+        Self {
+            depth: (data >> 16) as u8 as u32,
+            bound: match (data >> 24) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            value: (data >> 32) as u32 as ValueType,
+            best_move: if m.is_null() { None } else { Some(m) },
+        }
+    }
+}
+
+/// Fixed-size hash table of searched positions keyed by the Zobrist hash.
+///
+/// Every slot is a pair of atomics holding `key ^ data` and `data`, so the
+/// table can be shared between rayon threads without locks: a slot torn by a
+/// concurrent write simply fails the key check on probe.
+pub struct TranspositionTable {
+    slots: Vec<[AtomicU64; 2]>,
+}
+
+impl TranspositionTable {
+    /// Default table size in megabytes
+    pub const DEFAULT_SIZE_MB: usize = 16;
+
+    /// Allocates a table taking at most `size_mb` megabytes (at least one slot).
+    pub fn new(size_mb: usize) -> Self {
+        let len =
+            (size_mb * 1024 * 1024 / std::mem::size_of::<[AtomicU64; 2]>()).max(1);
+        Self {
+            slots: (0..len)
+                .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+                .collect(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> &[AtomicU64; 2] {
+        let i = (u128::from(key) * self.slots.len() as u128) >> 64;
+        &self.slots[i as usize]
+    }
+
+    fn load(&self, key: u64) -> Option<Entry> {
+        let [checksum, data] = self.slot(key);
+        let data = data.load(Ordering::Relaxed);
+        if data != 0 && checksum.load(Ordering::Relaxed) ^ data == key {
+            Some(Entry::unpack(data))
+        } else {
+            None
+        }
+    }
+
+    /// Entry of the position at `ply` from the root of the search. Mate values
+    /// are made relative to the root again, see `store`.
+    pub fn probe(&self, key: u64, ply: u32) -> Option<Entry> {
+        self.load(key).map(|x| Entry {
+            value: mate_from_root(x.value, -(ply as ValueType)),
+            ..x
+        })
+    }
+
+    /// Stores an entry of the position at `ply` from the root of the search.
+    /// Mate values count plies from the root, they are stored counting plies
+    /// from this position, so they stay right when it is reached by another
+    /// path or in a later search. A deeper entry for the same position is kept.
+    pub fn store(&self, key: u64, entry: Entry, ply: u32) {
+        if let Some(old) = self.load(key)
+            && old.depth > entry.depth
+        {
+            return;
+        }
+        let [checksum, data] = self.slot(key);
+        let packed = Entry {
+            value: mate_from_root(entry.value, ply as ValueType),
+            ..entry
+        }
+        .pack();
+        checksum.store(key ^ packed, Ordering::Relaxed);
+        data.store(packed, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for [checksum, data] in &self.slots {
+            checksum.store(0, Ordering::Relaxed);
+            data.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Moves a mate `value` `plies` further from the root
+fn mate_from_root(value: ValueType, plies: ValueType) -> ValueType {
+    if value >= MATE_BOUND {
+        value + plies
+    } else if value <= -MATE_BOUND {
+        value - plies
+    } else {
+        value
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SIZE_MB)
+    }
+}
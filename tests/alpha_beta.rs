@@ -0,0 +1,111 @@
+//! Mate distances found by the search and the keys of its positions
+
+use chess_alpha_beta::alpha_beta::{
+    EvaluatedMove, SearchOptions, iterative_deepening,
+};
+use chess_alpha_beta::board_value::plies_to_mate;
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::search_limits::SearchLimits;
+use chess_alpha_beta::transposition_table::{TranspositionTable, position_key};
+
+/// Returns true if the side to move mates within `plies` plies whatever the
+/// opponent does
+fn mates_within(board: &pleco::Board, plies: u32) -> bool {
+    board.generate_moves().iter().any(|&m| {
+        let mut experiment_board = board.clone();
+        experiment_board.apply_move(m);
+        if experiment_board.checkmate() {
+            return true;
+        }
+        let replies = experiment_board.generate_moves();
+        plies > 2
+            && !replies.is_empty()
+            && replies.iter().all(|&reply| {
+                let mut reply_board = experiment_board.clone();
+                reply_board.apply_move(reply);
+                mates_within(&reply_board, plies - 2)
+            })
+    })
+}
+
+/// Plies to the fastest forced mate found by brute force
+fn shortest_mate(board: &pleco::Board) -> u32 {
+    (1..)
+        .step_by(2)
+        .find(|&plies| mates_within(board, plies))
+        .unwrap()
+}
+
+fn search(
+    board: &pleco::Board,
+    depth: u32,
+    tt: &TranspositionTable,
+) -> EvaluatedMove {
+    iterative_deepening(
+        board,
+        &SearchLimits::depth(depth.try_into().unwrap()),
+        tt,
+        &SearchOptions {
+            seed: Some(1),
+            ..Default::default()
+        },
+        |_, _| {},
+    )
+    .unwrap()
+}
+
+/// Plies to mate, positive if White mates
+fn searched_mate(board: &pleco::Board, depth: u32, tt: &TranspositionTable) -> i32 {
+    plies_to_mate(search(board, depth, tt).value).unwrap()
+}
+
+const MATES: [(&str, i32); 4] = [
+    ("k7/8/2K5/8/8/8/8/1R6 w - - 0 1", 3),
+    ("2k5/8/8/2K5/8/8/8/7R w - - 0 1", 5),
+    ("7r/8/8/8/2k5/8/8/2K5 b - - 0 1", -5),
+    ("5k2/8/5K2/8/8/8/8/6Q1 w - - 0 1", 3),
+];
+
+#[test]
+fn mate_distance() {
+    for (fen, plies) in MATES {
+        let board = parse_fen(fen).unwrap();
+        assert_eq!(shortest_mate(&board), plies.unsigned_abs(), "{fen}");
+        let tt = TranspositionTable::new(1);
+        // Deeper than the mate, so the mating lines transpose into each other
+        assert_eq!(searched_mate(&board, 7, &tt), plies, "{fen}");
+        // Again with the positions of the first search in the table
+        assert_eq!(searched_mate(&board, 5, &tt), plies, "{fen}");
+    }
+}
+
+/// The table is kept between moves as in a game
+#[test]
+fn mate_distance_after_moves() {
+    for (fen, plies) in MATES {
+        let mut board = parse_fen(fen).unwrap();
+        let tt = TranspositionTable::new(1);
+        let best = search(&board, 7, &tt);
+        for &m in &best.pv[..2] {
+            board.apply_move(m);
+        }
+        let plies = plies - 2 * plies.signum();
+        assert_eq!(searched_mate(&board, 3, &tt), plies, "{fen}");
+    }
+}
+
+/// pleco's own hash is the same for these positions
+#[test]
+fn black_king_square_is_hashed() {
+    let keys: Vec<u64> = [
+        "7r/8/8/8/8/1k6/8/2K5 w - - 0 1",
+        "7r/8/8/8/8/2k5/8/2K5 w - - 0 1",
+        "7r/8/8/8/8/3k4/8/2K5 w - - 0 1",
+    ]
+    .iter()
+    .map(|fen| position_key(&parse_fen(fen).unwrap()))
+    .collect();
+    assert_ne!(keys[0], keys[1]);
+    assert_ne!(keys[1], keys[2]);
+    assert_ne!(keys[0], keys[2]);
+}
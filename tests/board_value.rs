@@ -0,0 +1,53 @@
+//! Game phase and the evaluation tapered by it
+
+use chess_alpha_beta::board_value::{MAX_PHASE, board_value, game_phase};
+use chess_alpha_beta::fen::parse_fen;
+
+#[test]
+fn phase_by_pieces() {
+    for (fen, phase) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            MAX_PHASE,
+        ),
+        ("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1", 0),
+        ("r2qk2r/pppppppp/8/8/8/8/PPPPPPPP/R2QK2R w KQkq - 0 1", 16),
+        ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", 1),
+        // Promoted queens, 56 without the limit
+        (
+            "qqqqkqqq/pppppppp/8/8/8/8/PPPPPPPP/QQQQKQQQ w - - 0 1",
+            MAX_PHASE,
+        ),
+    ] {
+        assert_eq!(game_phase(&parse_fen(fen).unwrap()), phase, "{fen}");
+    }
+}
+
+#[test]
+fn tapered_value() {
+    // All pieces: the middlegame score
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "qqqqkqqq/pppppppp/8/8/8/8/PPPPPPPP/QQQQKQQ1 w - - 0 1",
+    ] {
+        let board = parse_fen(fen).unwrap();
+        assert_eq!(board_value(&board, 0), board.psq().mg(), "{fen}");
+    }
+    // Only kings and pawns: the endgame score
+    for fen in [
+        "4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1",
+        "8/5k2/8/2P5/8/8/5K2/8 b - - 0 1",
+    ] {
+        let board = parse_fen(fen).unwrap();
+        assert_eq!(board_value(&board, 0), board.psq().eg(), "{fen}");
+    }
+    // Both in proportion to the phase
+    let board =
+        parse_fen("r2qk2r/pppppppp/8/8/4P3/8/PPPP1PPP/R2QK2R b KQkq - 0 1").unwrap();
+    let psq = board.psq();
+    assert_ne!(psq.mg(), psq.eg());
+    assert_eq!(
+        board_value(&board, 0),
+        (psq.mg() * 16 + psq.eg() * 8) / MAX_PHASE
+    );
+}
@@ -0,0 +1,202 @@
+//! Polyglot opening books
+
+use chess_alpha_beta::book::{
+    BookBuilder, BookEntry, BookSelection, OpeningBook, book_key, encode_move,
+    write_book,
+};
+use chess_alpha_beta::pgn::parse_pgn;
+use chess_alpha_beta::san::san_to_move;
+
+/// Keys from the description of the Polyglot book format
+#[test]
+fn polyglot_keys() {
+    for (moves, key) in [
+        (&[][..], 0x463b96181691fc9c),
+        (&["e4"], 0x823c9b50fd114196),
+        (&["e4", "d5"], 0x0756b94461c50fb0),
+        (&["e4", "d5", "e5"], 0x662fafb965db29d4),
+        (&["e4", "d5", "e5", "f5"], 0x22a48b5a8e47ff78),
+        (&["e4", "d5", "e5", "f5", "Ke2"], 0x652a607ca3f242c1),
+        (&["e4", "d5", "e5", "f5", "Ke2", "Kf7"], 0x00fdd303c946bdd9),
+        (&["a4", "b5", "h4", "b4", "c4"], 0x3c8123ea7b067637),
+        (
+            &["a4", "b5", "h4", "b4", "c4", "bxc3", "Ra3"],
+            0x5c3f9b829b279560,
+        ),
+    ] {
+        let mut board = pleco::Board::start_pos();
+        for san in moves {
+            board.apply_move(san_to_move(&board, san).unwrap());
+        }
+        assert_eq!(book_key(&board), key, "{moves:?}");
+    }
+}
+
+#[test]
+fn read_book() {
+    let entries = [
+        // e2e4 and d2d4 from the initial position
+        (0x463b96181691fc9c, 12 << 6 | 28, 3),
+        (0x463b96181691fc9c, 11 << 6 | 27, 5),
+        // e7e5 after 1.e4
+        (0x823c9b50fd114196, 52 << 6 | 36, 1),
+    ];
+    let bytes: Vec<u8> = entries
+        .iter()
+        .flat_map(|&(key, m, weight)| {
+            BookEntry {
+                key,
+                m,
+                weight,
+                learn: 0,
+            }
+            .to_bytes()
+        })
+        .collect();
+    let book = OpeningBook::from_bytes(&bytes).unwrap();
+    assert_eq!(book.len(), 3);
+
+    let mut board = pleco::Board::start_pos();
+    let moves: Vec<(String, u16)> = book
+        .moves(&board)
+        .iter()
+        .map(|(m, weight)| (m.to_string(), *weight))
+        .collect();
+    assert_eq!(moves, [("e2e4".to_string(), 3), ("d2d4".to_string(), 5)]);
+    let best = book.choose(&board, BookSelection::Best, None).unwrap();
+    assert_eq!(best.to_string(), "d2d4");
+
+    board.apply_move(san_to_move(&board, "e4").unwrap());
+    let m = book
+        .choose(&board, BookSelection::Weighted, Some(1))
+        .unwrap();
+    assert_eq!(m.to_string(), "e7e5");
+    board.apply_move(m);
+    assert!(book.moves(&board).is_empty());
+}
+
+#[test]
+fn unsorted_book() {
+    let bytes: Vec<u8> = [2, 1]
+        .into_iter()
+        .flat_map(|key| {
+            BookEntry {
+                key,
+                m: 0,
+                weight: 1,
+                learn: 0,
+            }
+            .to_bytes()
+        })
+        .collect();
+    assert!(OpeningBook::from_bytes(&bytes).is_err());
+    assert!(OpeningBook::from_bytes(&bytes[1..]).is_err());
+}
+
+const GAMES: &str = r#"
+[Result "1-0"]
+1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. O-O Nf6 1-0
+
+[Result "1/2-1/2"]
+1. e4 e5 2. Nf3 Nf6 1/2-1/2
+
+[Result "0-1"]
+1. e4 c5 0-1
+
+[Result "*"]
+1. d4 d5 *
+
+[Result "1-0"]
+1. e4 e5 2. Qh5 Ke7 3. Qxe5# 1-0
+"#;
+
+/// Builds a book of `GAMES` and reads it back from the file
+fn build_book(max_ply: usize, min_games: u32) -> (Vec<BookEntry>, OpeningBook) {
+    let mut builder = BookBuilder::default();
+    let added: Vec<bool> = parse_pgn(GAMES)
+        .unwrap()
+        .iter()
+        .map(|x| builder.add_game(x, max_ply).unwrap())
+        .collect();
+    assert_eq!(added, [true, true, true, false, true]);
+    let entries = builder.entries(min_games);
+    let path = std::env::temp_dir().join(format!(
+        "chess-alpha-beta-book-{}-{max_ply}-{min_games}.bin",
+        std::process::id()
+    ));
+    write_book(&path, &entries).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    (entries, OpeningBook::from_bytes(&bytes).unwrap())
+}
+
+/// Book moves of the position after `moves` in SAN with their weights
+fn book_moves(book: &OpeningBook, moves: &[&str]) -> Vec<(String, u16)> {
+    let mut board = pleco::Board::start_pos();
+    for san in moves {
+        board.apply_move(san_to_move(&board, san).unwrap());
+    }
+    book.moves(&board)
+        .iter()
+        .map(|(m, weight)| (m.to_string(), *weight))
+        .collect()
+}
+
+#[test]
+fn build_book_from_pgn() {
+    let (entries, book) = build_book(usize::MAX, 1);
+    assert!(entries.is_sorted_by_key(|x| x.key));
+    assert_eq!(book.len(), entries.len());
+
+    // Two wins, a draw and a loss
+    assert_eq!(book_moves(&book, &[]), [("e2e4".to_string(), 5)]);
+    // Best moves first: a win, then a draw and two losses
+    assert_eq!(
+        book_moves(&book, &["e4"]),
+        [("c7c5".to_string(), 2), ("e7e5".to_string(), 1)]
+    );
+    assert_eq!(
+        book_moves(&book, &["e4", "e5"]),
+        [("g1f3".to_string(), 3), ("d1h5".to_string(), 2)]
+    );
+    // Lost moves have no points and are left out
+    assert_eq!(
+        book_moves(&book, &["e4", "e5", "Nf3"]),
+        [("g8f6".to_string(), 1)]
+    );
+    assert!(book_moves(&book, &["e4", "e5", "Qh5"]).is_empty());
+}
+
+#[test]
+fn castling_is_king_to_rook() {
+    let (entries, book) = build_book(usize::MAX, 1);
+    let mut board = pleco::Board::start_pos();
+    for san in ["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5"] {
+        board.apply_move(san_to_move(&board, san).unwrap());
+    }
+    let castling = san_to_move(&board, "O-O").unwrap();
+    // e1 = 4, h1 = 7
+    assert_eq!(encode_move(castling), 4 << 6 | 7);
+    let key = book_key(&board);
+    let position: Vec<&BookEntry> =
+        entries.iter().filter(|x| x.key == key).collect();
+    assert_eq!(position.len(), 1);
+    assert_eq!(position[0].m, 4 << 6 | 7);
+    assert_eq!(
+        book.choose(&board, BookSelection::Best, None),
+        Some(castling)
+    );
+}
+
+#[test]
+fn filter_by_games_and_ply() {
+    let (_, book) = build_book(usize::MAX, 2);
+    assert_eq!(book_moves(&book, &[]), [("e2e4".to_string(), 5)]);
+    assert_eq!(book_moves(&book, &["e4"]), [("e7e5".to_string(), 1)]);
+    assert_eq!(book_moves(&book, &["e4", "e5"]), [("g1f3".to_string(), 3)]);
+    assert!(book_moves(&book, &["e4", "e5", "Nf3"]).is_empty());
+
+    let (_, book) = build_book(2, 1);
+    assert_eq!(book_moves(&book, &["e4"]).len(), 2);
+    assert!(book_moves(&book, &["e4", "e5"]).is_empty());
+}
@@ -0,0 +1,55 @@
+//! Draws by the rules which the players must detect themselves
+
+use chess_alpha_beta::draw::{DrawReason, draw_reason, insufficient_material};
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::san::san_to_move;
+
+fn play(board: &mut pleco::Board, moves: &[&str]) {
+    for san in moves {
+        let m = san_to_move(board, san).unwrap();
+        board.apply_move(m);
+    }
+}
+
+#[test]
+fn threefold_repetition() {
+    let mut board = pleco::Board::start_pos();
+    play(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+    assert_eq!(draw_reason(&board), None);
+    play(&mut board, &["Nf3", "Nf6", "Ng1"]);
+    assert_eq!(draw_reason(&board), None);
+    play(&mut board, &["Ng8"]);
+    assert_eq!(draw_reason(&board), Some(DrawReason::ThreefoldRepetition));
+}
+
+#[test]
+fn fifty_move_rule() {
+    let mut board = parse_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    assert_eq!(draw_reason(&board), None);
+    play(&mut board, &["Ra2"]);
+    assert_eq!(board.rule_50(), 100);
+    assert_eq!(draw_reason(&board), Some(DrawReason::FiftyMoveRule));
+}
+
+#[test]
+fn checkmate_on_the_fiftieth_move() {
+    let mut board = parse_fen("k7/8/1K6/8/8/8/8/7R w - - 99 80").unwrap();
+    play(&mut board, &["Rh8#"]);
+    assert_eq!(board.rule_50(), 100);
+    assert!(board.checkmate());
+    assert_eq!(draw_reason(&board), None);
+}
+
+#[test]
+fn bishops_on_the_same_color() {
+    let board = parse_fen("4k3/8/8/2b5/8/8/3B4/4K3 w - - 0 1").unwrap();
+    assert!(insufficient_material(&board));
+    assert_eq!(draw_reason(&board), Some(DrawReason::InsufficientMaterial));
+}
+
+#[test]
+fn bishops_on_opposite_colors() {
+    let board = parse_fen("4k3/8/8/3b4/8/8/3B4/4K3 w - - 0 1").unwrap();
+    assert!(!insufficient_material(&board));
+    assert_eq!(draw_reason(&board), None);
+}
@@ -0,0 +1,62 @@
+//! Positions read from Forsyth-Edwards Notation
+
+use chess_alpha_beta::fen::parse_fen;
+
+#[test]
+fn valid_positions() {
+    for (fen, halfmove, moves_played) in [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            0,
+            0,
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            0,
+            1,
+        ),
+        ("4k3/8/8/8/8/8/8/4K2R w K - 7 40", 7, 78),
+        ("4k3/8/8/8/8/8/8/4K3 b - - 99 16000", 99, 31999),
+        // Move counters left out
+        ("4k3/8/8/8/8/8/8/4K3 w - -", 0, 0),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 5", 5, 0),
+    ] {
+        let board = parse_fen(fen).unwrap();
+        assert_eq!(board.rule_50(), halfmove, "{fen}");
+        assert_eq!(board.moves_played(), moves_played, "{fen}");
+    }
+}
+
+#[test]
+fn invalid_positions() {
+    for fen in [
+        "",
+        "4k3/8/8/8/8/8/8/4K3",
+        "4k3/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+        "8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w K - 0 1",
+        "4k3/8/8/8/8/8/8/R3K3 w X - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - e9 0 1",
+        // Black king in check with White to move
+        "4k3/4R3/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra",
+    ] {
+        assert!(parse_fen(fen).is_err(), "{fen}");
+    }
+}
+
+/// Counters which would overflow the move counters of pleco
+#[test]
+fn invalid_move_counters() {
+    for counters in [
+        "-1 1", "x 1", "- 1", "32001 1", "0 0", "0 -1", "0 x", "0 -", "0 16001",
+        "0 32768", "0 40000",
+    ] {
+        let fen = format!("4k3/8/8/8/8/8/8/4K3 w - - {counters}");
+        assert!(parse_fen(&fen).is_err(), "{fen}");
+    }
+}
@@ -0,0 +1,86 @@
+//! Games written to PGN and read back
+
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::pgn::{PgnHeaders, parse_pgn, write_pgn};
+use chess_alpha_beta::san::san_to_move;
+
+const GAMES: &str = r#"[Event "First"]
+[White "Player \"A\""]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4 (2... d5 {counter
+gambit}) 3. Nf3) Nc6 $1 3. Bb5 ; Ruy Lopez (1. d4)
+a6 4.Ba4 Nf6 5. O-O! 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 12"]
+[Result "1/2-1/2"]
+
+12...e5 13. Nf3 $2 Nc6 1/2-1/2
+
+1. d4 d5"#;
+
+#[test]
+fn parse_games() {
+    let games = parse_pgn(GAMES).unwrap();
+    assert_eq!(games.len(), 3);
+
+    assert_eq!(games[0].tag("Event"), Some("First"));
+    assert_eq!(games[0].tag("White"), Some("Player \"A\""));
+    assert_eq!(games[0].tag("Round"), None);
+    assert_eq!(
+        games[0].moves,
+        ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O!"]
+    );
+    assert_eq!(games[0].result, "1-0");
+    assert_eq!(
+        games[0].replay().unwrap().fen(),
+        "r1bqkb1r/1ppp1ppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 5"
+    );
+
+    assert_eq!(games[1].moves, ["e5", "Nf3", "Nc6"]);
+    assert_eq!(games[1].result, "1/2-1/2");
+    assert_eq!(games[1].replay().unwrap().depth(), 3);
+
+    // Game without a result at the end of the file
+    assert_eq!(games[2].moves, ["d4", "d5"]);
+    assert_eq!(games[2].result, "*");
+}
+
+#[test]
+fn unbalanced_variation() {
+    assert!(parse_pgn("1. e4 e5 2. Nf3) Nc6 *").is_err());
+}
+
+fn check_round_trip(mut board: pleco::Board, moves: &[&str]) {
+    for san in moves {
+        let m = san_to_move(&board, san).unwrap();
+        board.apply_move(m);
+    }
+    let pgn = write_pgn(&board, &PgnHeaders::default(), &[]);
+    let games = parse_pgn(&pgn).unwrap();
+    assert_eq!(games.len(), 1, "{pgn}");
+    assert_eq!(games[0].moves, moves, "{pgn}");
+    assert_eq!(games[0].replay().unwrap().fen(), board.fen(), "{pgn}");
+}
+
+#[test]
+fn round_trip() {
+    check_round_trip(
+        pleco::Board::start_pos(),
+        &[
+            "e4", "d5", "exd5", "Nf6", "Bb5+", "c6", "dxc6", "Qb6", "cxb7+", "Kd8",
+            "bxa8=Q", "Bd7", "Nf3", "e5", "O-O", "Bd6", "Qxb8+", "Bc8", "Qxc8+",
+        ],
+    );
+}
+
+#[test]
+fn round_trip_from_fen() {
+    check_round_trip(
+        parse_fen("r3k2r/8/8/8/3pP3/8/8/R3K2R b KQkq e3 0 30").unwrap(),
+        &["dxe3", "O-O-O", "e2", "Rde1", "O-O", "Rxe2"],
+    );
+}
@@ -0,0 +1,81 @@
+//! Moves written in Standard Algebraic Notation and read back
+
+use chess_alpha_beta::fen::parse_fen;
+use chess_alpha_beta::san::{move_to_san, san_to_move};
+
+/// Positions of tests/perft.rs with castling, en passant, promotions and
+/// pieces which need disambiguation
+const POSITIONS: [&str; 7] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+
+/// Checks every legal move of `board` and, while `depth` > 1, of the positions
+/// after them
+fn check_round_trip(board: &pleco::Board, depth: u32) {
+    for &m in board.generate_moves().iter() {
+        let san = move_to_san(board, m);
+        assert_eq!(
+            san_to_move(board, &san).unwrap(),
+            m,
+            "{san} in {}",
+            board.fen()
+        );
+        // Spellings which are accepted but never written
+        let variants = [
+            san.replace('O', "0"),
+            san.replace('=', ""),
+            format!("{}!?", san.trim_end_matches(['+', '#'])),
+        ];
+        for variant in variants {
+            assert_eq!(
+                san_to_move(board, &variant).unwrap(),
+                m,
+                "{variant} in {}",
+                board.fen()
+            );
+        }
+        if depth > 1 {
+            let mut experiment_board = board.clone();
+            experiment_board.apply_move(m);
+            check_round_trip(&experiment_board, depth - 1);
+        }
+    }
+}
+
+#[test]
+fn round_trip() {
+    for fen in POSITIONS {
+        check_round_trip(&parse_fen(fen).unwrap(), 2);
+    }
+}
+
+#[test]
+fn disambiguation() {
+    let board = parse_fen("4k3/8/8/8/1N3N2/8/1N6/4K3 w - - 0 1").unwrap();
+    let sans: Vec<String> = board
+        .generate_moves()
+        .iter()
+        .filter(|m| m.get_dest().to_string() == "d3")
+        .map(|&m| move_to_san(&board, m))
+        .collect();
+    assert_eq!(sans.len(), 3);
+    for san in ["Nfd3", "Nb4d3", "N2d3"] {
+        assert!(sans.contains(&san.to_string()), "{san} not in {sans:?}");
+    }
+    assert!(san_to_move(&board, "Nd3").is_err());
+    assert!(san_to_move(&board, "Nbd3").is_err());
+}
+
+#[test]
+fn invalid_moves() {
+    let board = pleco::Board::start_pos();
+    for san in ["", "e5", "Ke2", "O-O", "Nf3e", "xx", "e8=Q"] {
+        assert!(san_to_move(&board, san).is_err(), "{san}");
+    }
+}